@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use std::str::FromStr;
+
+use castor_core::gemini::link::Link;
+
+fuzz_target!(|line: &str| {
+    let _ = Link::from_str(line);
+});
@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use std::str::FromStr;
+
+use castor_core::gemini::link::Link;
+use castor_core::gemini::parser::{self, TextElement};
+
+// A whole page, with its links parsed like the browser does.
+fuzz_target!(|data: &[u8]| {
+    for element in parser::parse(String::from_utf8_lossy(data).to_string()) {
+        if let Ok(TextElement::LinkItem(line)) = element {
+            let _ = Link::from_str(&line);
+        }
+    }
+});
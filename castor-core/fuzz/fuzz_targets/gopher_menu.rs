@@ -0,0 +1,22 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use std::str::FromStr;
+
+use castor_core::gopher::link::Link;
+use castor_core::gopher::parser::{self, TextElement};
+
+// A whole menu, with its items parsed like the browser does.
+fuzz_target!(|data: &[u8]| {
+    for element in parser::parse(String::from_utf8_lossy(data).to_string()) {
+        match element {
+            Ok(TextElement::LinkItem(line))
+            | Ok(TextElement::ExternalLinkItem(line))
+            | Ok(TextElement::Image(line))
+            | Ok(TextElement::Binary(line))
+            | Ok(TextElement::Search(line)) => {
+                let _ = Link::from_str(&line);
+            }
+            _ => (),
+        }
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use url::Url;
+
+use castor_core::html;
+
+// Any page, read from a Gemini and a Gopher address.
+fuzz_target!(|data: &[u8]| {
+    let content = String::from_utf8_lossy(data);
+    for url in &["gemini://localhost/index.html", "gopher://localhost/h/index.html"] {
+        let _ = html::gemtext(&Url::parse(url).unwrap(), &content);
+    }
+});
@@ -0,0 +1,284 @@
+use crate::About;
+use crate::File;
+use crate::Finger;
+use crate::Gemini;
+use crate::Gopher;
+use crate::Nex;
+use crate::Protocol;
+use crate::Spartan;
+use crate::Titan;
+use url::Url;
+
+// Links are resolved against the page being viewed, when there is one.
+pub trait AbsoluteUrl {
+    fn to_absolute_url(&self, current: Option<&Url>) -> Result<url::Url, url::ParseError>;
+}
+
+fn current_host(current: Option<&Url>) -> Option<String> {
+    current.and_then(|u| u.host_str()).map(String::from)
+}
+
+// The directory of the current page.
+fn current_path(current: Option<&Url>) -> Option<String> {
+    current
+        .and_then(|u| u.join("./").ok())
+        .map(|p| p.to_string())
+}
+
+impl AbsoluteUrl for About {
+    fn to_absolute_url(&self, _current: Option<&Url>) -> Result<url::Url, url::ParseError> {
+        Url::parse(self.get_source_str())
+    }
+}
+
+impl AbsoluteUrl for Finger {
+    fn to_absolute_url(&self, _current: Option<&Url>) -> Result<url::Url, url::ParseError> {
+        Ok(self.get_source_url())
+    }
+}
+
+impl AbsoluteUrl for File {
+    fn to_absolute_url(&self, current: Option<&Url>) -> Result<url::Url, url::ParseError> {
+        let url = self.get_source_str();
+        if url.starts_with("file://") {
+            return Url::parse(url);
+        }
+
+        // Relative links are resolved against the directory being viewed
+        match current_path(current) {
+            Some(current) if current.starts_with("file://") => Url::parse(&current)?.join(url),
+            _ => Url::from_file_path(url).map_err(|_| url::ParseError::RelativeUrlWithoutBase),
+        }
+    }
+}
+
+impl AbsoluteUrl for Titan {
+    fn to_absolute_url(&self, _current: Option<&Url>) -> Result<url::Url, url::ParseError> {
+        Ok(self.get_source_url())
+    }
+}
+
+impl AbsoluteUrl for Gemini {
+    fn to_absolute_url(&self, current: Option<&Url>) -> Result<url::Url, url::ParseError> {
+        let url = self.get_source_str();
+        // Creates an absolute link if needed
+        match current_host(current) {
+            Some(host) => {
+                if url.starts_with("gemini://") {
+                    Url::parse(&url)
+                } else if url.starts_with("//") {
+                    Url::parse(&format!("gemini:{}", url))
+                } else if url.starts_with('/') {
+                    Url::parse(&format!("gemini://{}{}", host, url))
+                } else {
+                    let current_host_path = current_path(current).unwrap();
+                    Url::parse(&format!("{}{}", current_host_path, url))
+                }
+            }
+            None => {
+                if url.starts_with("gemini://") {
+                    Url::parse(&url)
+                } else if url.starts_with("//") {
+                    Url::parse(&format!("gemini:{}", url))
+                } else {
+                    Url::parse(url)
+                }
+            }
+        }
+    }
+}
+
+impl AbsoluteUrl for Gopher {
+    fn to_absolute_url(&self, current: Option<&Url>) -> Result<url::Url, url::ParseError> {
+        let url = self.get_source_str();
+        // Relative links stay on TLS when the current page was loaded with it
+        let scheme = match current {
+            Some(current) if current.scheme() == "gophers" => "gophers",
+            _ => "gopher",
+        };
+        // Creates an absolute link if needed
+        match current_host(current) {
+            Some(host) => {
+                if url.starts_with("gopher://") || url.starts_with("gophers://") {
+                    Url::parse(&url)
+                } else if url.starts_with("//") {
+                    Url::parse(&format!("{}:{}", scheme, url))
+                } else if url.starts_with('/') {
+                    Url::parse(&format!("{}://{}{}", scheme, host, url))
+                } else {
+                    let current_host_path = current_path(current).unwrap();
+                    Url::parse(&format!("{}{}", current_host_path, url))
+                }
+            }
+            None => {
+                if url.starts_with("gopher://") || url.starts_with("gophers://") {
+                    Url::parse(&url)
+                } else if url.starts_with("//") {
+                    Url::parse(&format!("gopher:{}", url))
+                } else {
+                    Url::parse(&format!("gopher://{}", url))
+                }
+            }
+        }
+    }
+}
+
+impl AbsoluteUrl for Nex {
+    fn to_absolute_url(&self, current: Option<&Url>) -> Result<url::Url, url::ParseError> {
+        let url = self.get_source_str();
+        // Creates an absolute link if needed
+        match current_host(current) {
+            Some(host) => {
+                if url.starts_with("nex://") {
+                    Url::parse(url)
+                } else if url.starts_with("//") {
+                    Url::parse(&format!("nex:{}", url))
+                } else if url.starts_with('/') {
+                    Url::parse(&format!("nex://{}{}", host, url))
+                } else {
+                    let current_host_path = current_path(current).unwrap();
+                    Url::parse(&format!("{}{}", current_host_path, url))
+                }
+            }
+            None => {
+                if url.starts_with("nex://") {
+                    Url::parse(url)
+                } else if url.starts_with("//") {
+                    Url::parse(&format!("nex:{}", url))
+                } else {
+                    Url::parse(url)
+                }
+            }
+        }
+    }
+}
+
+impl AbsoluteUrl for Spartan {
+    fn to_absolute_url(&self, current: Option<&Url>) -> Result<url::Url, url::ParseError> {
+        let url = self.get_source_str();
+        // Creates an absolute link if needed
+        match current_host(current) {
+            Some(host) => {
+                if url.starts_with("spartan://") {
+                    Url::parse(url)
+                } else if url.starts_with("//") {
+                    Url::parse(&format!("spartan:{}", url))
+                } else if url.starts_with('/') {
+                    Url::parse(&format!("spartan://{}{}", host, url))
+                } else {
+                    let current_host_path = current_path(current).unwrap();
+                    Url::parse(&format!("{}{}", current_host_path, url))
+                }
+            }
+            None => {
+                if url.starts_with("spartan://") {
+                    Url::parse(url)
+                } else if url.starts_with("//") {
+                    Url::parse(&format!("spartan:{}", url))
+                } else {
+                    Url::parse(url)
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_make_absolute_slash_path_no_current_host() {
+    let url = "/foo";
+    let absolute_url = Gemini {
+        source: String::from(url),
+    }
+    .to_absolute_url(None);
+    assert_eq!(absolute_url, Err(url::ParseError::RelativeUrlWithoutBase));
+}
+#[test]
+fn test_make_absolute_just_path_no_current_host() {
+    let url = "foo";
+    let absolute_url = Gemini {
+        source: String::from(url),
+    }
+    .to_absolute_url(None);
+    assert_eq!(absolute_url, Err(url::ParseError::RelativeUrlWithoutBase));
+}
+#[test]
+fn test_make_absolute_full_url() {
+    let current = Url::parse("gemini://typed-hole.org").unwrap();
+    let url = "gemini://typed-hole.org/foo";
+    let expected_url = Url::parse("gemini://typed-hole.org/foo").unwrap();
+    let absolute_url = Gemini {
+        source: String::from(url),
+    }
+    .to_absolute_url(Some(&current))
+    .unwrap();
+    assert_eq!(expected_url, absolute_url);
+}
+#[test]
+fn test_make_absolute_full_url_no_protocol() {
+    let current = Url::parse("gemini://typed-hole.org").unwrap();
+    let url = "//typed-hole.org/foo";
+    let expected_url = Url::parse("gemini://typed-hole.org/foo").unwrap();
+    let absolute_url = Gemini {
+        source: String::from(url),
+    }
+    .to_absolute_url(Some(&current))
+    .unwrap();
+    assert_eq!(expected_url, absolute_url);
+}
+#[test]
+fn test_make_absolute_slash_path() {
+    let current = Url::parse("gemini://typed-hole.org").unwrap();
+    let url = "/foo";
+    let expected_url = Url::parse("gemini://typed-hole.org/foo").unwrap();
+    let absolute_url = Gemini {
+        source: String::from(url),
+    }
+    .to_absolute_url(Some(&current))
+    .unwrap();
+    assert_eq!(expected_url, absolute_url);
+}
+#[test]
+fn test_make_absolute_just_path() {
+    let current = Url::parse("gemini://typed-hole.org").unwrap();
+    let url = "foo";
+    let expected_url = Url::parse("gemini://typed-hole.org/foo").unwrap();
+    let absolute_url = Gemini {
+        source: String::from(url),
+    }
+    .to_absolute_url(Some(&current))
+    .unwrap();
+    assert_eq!(expected_url, absolute_url);
+}
+#[test]
+fn test_make_absolute_full_url_no_current_host() {
+    let url = "gemini://typed-hole.org/foo";
+    let expected_url = Url::parse("gemini://typed-hole.org/foo").unwrap();
+    let absolute_url = Gemini {
+        source: String::from(url),
+    }
+    .to_absolute_url(None)
+    .unwrap();
+    assert_eq!(expected_url, absolute_url);
+}
+#[test]
+fn test_make_absolute_full_url_no_protocol_no_current_host() {
+    let url = "//typed-hole.org/foo";
+    let expected_url = Url::parse("gemini://typed-hole.org/foo").unwrap();
+    let absolute_url = Gemini {
+        source: String::from(url),
+    }
+    .to_absolute_url(None)
+    .unwrap();
+    assert_eq!(expected_url, absolute_url);
+}
+#[test]
+fn test_make_absolute_file_path() {
+    let current = Url::parse("file:///home/capsule/index.gmi").unwrap();
+    let expected_url = Url::parse("file:///home/capsule/posts/first.gmi").unwrap();
+    let absolute_url = File {
+        source: String::from("posts/first.gmi"),
+    }
+    .to_absolute_url(Some(&current))
+    .unwrap();
+    assert_eq!(expected_url, absolute_url);
+}
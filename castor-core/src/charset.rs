@@ -0,0 +1,88 @@
+// Old gopher holes and finger plans are often written in CP437 or Latin-1
+// rather than UTF-8. The charset is guessed from the first bytes that aren't
+// ASCII, unless the settings pick one for the host.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Charset {
+    Utf8,
+    Latin1,
+    Cp437,
+}
+
+impl Charset {
+    pub fn from_name(name: &str) -> Option<Charset> {
+        match name.to_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(Charset::Utf8),
+            "latin1" | "latin-1" | "iso-8859-1" => Some(Charset::Latin1),
+            "cp437" | "ibm437" | "437" => Some(Charset::Cp437),
+            _ => None,
+        }
+    }
+}
+
+// The upper half of code page 437, from 0x80 to 0xFF.
+const CP437: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', 'É', 'æ', 'Æ',
+    'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', 'á', 'í', 'ó', 'ú', 'ñ', 'Ñ',
+    'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕',
+    '╣', '║', '╗', '╝', '╜', '╛', '┐', '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦',
+    '╠', '═', '╬', '╧', '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐',
+    '▀', 'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', '≡', '±',
+    '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}',
+];
+
+// None while there's only ASCII to go on.
+pub fn detect(data: &[u8]) -> Option<Charset> {
+    let high: Vec<&u8> = data.iter().filter(|b| **b >= 0x80).collect();
+    if high.is_empty() {
+        return None;
+    }
+    if std::str::from_utf8(data).is_ok() {
+        return Some(Charset::Utf8);
+    }
+
+    // 0xB0 to 0xDF are shades and box drawing in CP437, but mostly capital
+    // letters with accents in Latin-1 which are rare in running text
+    let boxes = high.iter().filter(|b| (0xB0..=0xDF).contains(**b)).count();
+    if boxes * 2 > high.len() {
+        Some(Charset::Cp437)
+    } else {
+        Some(Charset::Latin1)
+    }
+}
+
+pub fn decode(data: &[u8], charset: Charset) -> String {
+    match charset {
+        Charset::Utf8 => String::from_utf8_lossy(data).to_string(),
+        Charset::Latin1 => data.iter().map(|b| *b as char).collect(),
+        Charset::Cp437 => data
+            .iter()
+            .map(|b| {
+                if *b < 0x80 {
+                    *b as char
+                } else {
+                    CP437[(*b - 0x80) as usize]
+                }
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn test_detect() {
+    assert_eq!(detect(b"plain ascii\n"), None);
+    assert_eq!(detect("caf\u{e9}\n".as_bytes()), Some(Charset::Utf8));
+    assert_eq!(detect(b"caf\xe9 cr\xe8me\n"), Some(Charset::Latin1));
+    assert_eq!(
+        detect(b"\xc9\xcd\xcd\xbb\n\xba  \xba\n\xc8\xcd\xcd\xbc\n"),
+        Some(Charset::Cp437)
+    );
+}
+
+#[test]
+fn test_decode() {
+    let art = b"\xc9\xcd\xcd\xbb\n\xb0\xb1\xb2\xdb\n";
+    assert_eq!(decode(art, Charset::Cp437), "╔══╗\n░▒▓█\n");
+    assert_eq!(decode(b"caf\xe9", Charset::Latin1), "café");
+    assert_eq!(decode(b"\x82t\x82", Charset::Cp437), "été");
+}
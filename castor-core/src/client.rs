@@ -0,0 +1,199 @@
+use std::io::Read;
+use std::str::FromStr;
+use url::Url;
+
+use crate::protocols::{File, Gemini, Gopher, Nex, Spartan};
+use crate::status::Status;
+
+pub trait Client {
+    fn get_data(&self) -> Result<(Option<Vec<u8>>, Vec<u8>), String>;
+}
+
+// A response body that is still being received.
+pub type Stream = Box<dyn Read + Send>;
+
+const CHUNK_SIZE: usize = 16 * 1024;
+
+pub enum Chunk {
+    Data(Vec<u8>),
+    Done,
+    TooLarge(Stream),
+    Error(String),
+}
+
+// Reads the body chunk by chunk and hands every chunk to `send` as soon as it
+// arrives. Once more than `max_size` bytes were read, the rest of the stream
+// is handed over untouched so the caller can decide what to do with it.
+// Stops early when `send` returns false (nobody is listening anymore).
+pub fn read_chunks<F: FnMut(Chunk) -> bool>(mut stream: Stream, max_size: usize, mut send: F) {
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut received = 0;
+
+    loop {
+        match stream.read(&mut buffer) {
+            Ok(0) => {
+                send(Chunk::Done);
+                return;
+            }
+            Ok(size) => {
+                received += size;
+                if !send(Chunk::Data(buffer[..size].to_vec())) {
+                    return;
+                }
+                if received > max_size {
+                    send(Chunk::TooLarge(stream));
+                    return;
+                }
+            }
+            Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+            // the read timeout of the socket counts from the last chunk
+            Err(ref e)
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut =>
+            {
+                send(Chunk::Error(format!(
+                    "Nothing received for {} seconds",
+                    crate::settings::read_timeout().as_secs()
+                )));
+                return;
+            }
+            Err(e) => {
+                send(Chunk::Error(e.to_string()));
+                return;
+            }
+        }
+    }
+}
+
+const MAX_REDIRECTS: usize = 5;
+
+// Fetches a whole resource to be shown inside the current page, following
+// Gemini and Spartan redirects. Returns the MIME type when the protocol has one.
+pub fn fetch(url: &Url, max_size: usize) -> Result<(String, Vec<u8>), String> {
+    let origin = url.scheme().to_string();
+    let mut url = url.clone();
+
+    for _ in 0..MAX_REDIRECTS {
+        match url.scheme() {
+            "gemini" => {
+                let (meta, body) = crate::gemini::client::get_stream(Gemini {
+                    source: url.to_string(),
+                })?;
+                let meta = String::from_utf8_lossy(&meta.unwrap_or_default()).to_string();
+
+                match Status::from_str(&meta) {
+                    Ok(Status::Success(mime)) => return Ok((mime, read_all(body, max_size)?)),
+                    Ok(Status::RedirectTemporary(new_url))
+                    | Ok(Status::RedirectPermanent(new_url)) => {
+                        url = url.join(&new_url).map_err(|e| e.to_string())?;
+                    }
+                    _ => return Err(format!("Unexpected response: {}", meta.trim())),
+                }
+            }
+            "gopher" | "gophers" => {
+                let (_meta, body) = crate::gopher::client::get_stream(Gopher {
+                    source: url.to_string(),
+                })?;
+                return Ok((String::new(), read_all(body, max_size)?));
+            }
+            "nex" => {
+                let (_meta, body) = crate::nex::client::get_stream(Nex {
+                    source: url.to_string(),
+                })?;
+                return Ok((String::new(), read_all(body, max_size)?));
+            }
+            "spartan" => {
+                let (meta, body) = crate::spartan::client::get_stream(Spartan {
+                    source: url.to_string(),
+                })?;
+                let meta = String::from_utf8_lossy(&meta.unwrap_or_default()).to_string();
+
+                match crate::spartan::status::Status::from_str(&meta) {
+                    Ok(crate::spartan::status::Status::Success(mime)) => {
+                        return Ok((mime, read_all(body, max_size)?))
+                    }
+                    Ok(crate::spartan::status::Status::Redirect(path)) => {
+                        url = url.join(&path).map_err(|e| e.to_string())?;
+                    }
+                    _ => return Err(format!("Unexpected response: {}", meta.trim())),
+                }
+            }
+            // a remote page can't redirect to a local file
+            "file" if origin != "file" => {
+                return Err(String::from("Can't follow a redirect to a local file"))
+            }
+            "file" => {
+                let (meta, body) = crate::file::client::get_stream(File {
+                    source: url.to_string(),
+                })?;
+                let mime = String::from_utf8_lossy(&meta.unwrap_or_default()).to_string();
+                return Ok((mime, read_all(body, max_size)?));
+            }
+            scheme => return Err(format!("Can't fetch {} URLs", scheme)),
+        }
+    }
+    Err(String::from("Too many redirects"))
+}
+
+fn read_all(body: Stream, max_size: usize) -> Result<Vec<u8>, String> {
+    let mut content = vec![];
+    body.take(max_size as u64 + 1)
+        .read_to_end(&mut content)
+        .map_err(|e| format!("Failed to read response\n{}", e))?;
+
+    if content.len() > max_size {
+        Err(format!("Response is larger than {}", format_size(max_size)))
+    } else {
+        Ok(content)
+    }
+}
+
+pub fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+#[test]
+fn test_read_chunks_until_done() {
+    let body: Stream = Box::new(std::io::Cursor::new(b"# Title\nSome text\n".to_vec()));
+    let mut received = vec![];
+    let mut done = false;
+
+    read_chunks(body, 1024, |chunk| {
+        match chunk {
+            Chunk::Data(data) => received.extend(data),
+            Chunk::Done => done = true,
+            _ => panic!("unexpected chunk"),
+        }
+        true
+    });
+
+    assert_eq!(received, b"# Title\nSome text\n".to_vec());
+    assert!(done);
+}
+
+#[test]
+fn test_read_chunks_too_large() {
+    let body: Stream = Box::new(std::io::Cursor::new(vec![b'a'; CHUNK_SIZE * 3]));
+    let mut received = vec![];
+    let mut rest = vec![];
+
+    read_chunks(body, CHUNK_SIZE, |chunk| {
+        match chunk {
+            Chunk::Data(data) => received.extend(data),
+            Chunk::TooLarge(mut stream) => {
+                stream.read_to_end(&mut rest).unwrap();
+            }
+            _ => panic!("unexpected chunk"),
+        }
+        true
+    });
+
+    assert!(received.len() > CHUNK_SIZE);
+    assert_eq!(received.len() + rest.len(), CHUNK_SIZE * 3);
+}
@@ -0,0 +1,75 @@
+// Gemini feeds: the entries of a gemtext page are its links whose label
+// starts with a YYYY-MM-DD date, its title is the first level 1 heading.
+use url::Url;
+
+use super::{is_date, Entry, Feed};
+use crate::gemini::parser::{self, TextElement};
+
+pub fn parse(url: &Url, content: &str) -> Result<Feed, String> {
+    let mut title = None;
+    let mut entries = vec![];
+
+    for element in parser::parse(content.to_string()) {
+        match element {
+            Ok(TextElement::H1(heading)) if title.is_none() => title = Some(heading),
+            Ok(TextElement::LinkItem(line)) => entries.extend(entry(url, &line)),
+            _ => (),
+        }
+    }
+
+    if entries.is_empty() {
+        return Err(format!("{} has no dated links, it's not a feed", url));
+    }
+    Ok(Feed {
+        title: title.unwrap_or_else(|| url.to_string()),
+        entries,
+    })
+}
+
+// => URL YYYY-MM-DD Title, with an optional separator after the date.
+fn entry(base: &Url, line: &str) -> Option<Entry> {
+    let mut parts = line
+        .trim_start_matches("=>")
+        .trim()
+        .splitn(2, char::is_whitespace);
+    let target = parts.next()?;
+    let label = parts.next().unwrap_or("").trim();
+
+    let date = label.get(..10).filter(|date| is_date(date))?;
+    let title = label[10..]
+        .trim_start_matches(|c: char| c.is_whitespace() || c == '-' || c == ':')
+        .trim();
+
+    Some(Entry {
+        url: base.join(target).ok()?,
+        title: if title.is_empty() { target } else { title }.to_string(),
+        date: date.to_string(),
+        summary: String::new(),
+    })
+}
+
+#[test]
+fn test_parse() {
+    let url = Url::parse("gemini://typed-hole.org/gemlog/").unwrap();
+    let content = "# Julien's gemlog
+## Posts
+=> 2020-05-17-castor.gmi 2020-05-17 - Castor 0.8
+=> gemini://typed-hole.org/old.gmi 2019-12-01 Older post
+=> /about.gmi About me
+=> 2020-06-01.gmi 2020-06-01
+";
+    let feed = parse(&url, content).unwrap();
+
+    assert_eq!(feed.title, "Julien's gemlog");
+    assert_eq!(feed.entries.len(), 3);
+    assert_eq!(
+        feed.entries[0].url.as_str(),
+        "gemini://typed-hole.org/gemlog/2020-05-17-castor.gmi"
+    );
+    assert_eq!(feed.entries[0].title, "Castor 0.8");
+    assert_eq!(feed.entries[0].date, "2020-05-17");
+    assert_eq!(feed.entries[1].title, "Older post");
+    assert_eq!(feed.entries[2].title, "2020-06-01.gmi");
+
+    assert!(parse(&url, "# Home\n=> /about.gmi About me\n").is_err());
+}
@@ -0,0 +1,130 @@
+// Gemlogs and Atom/RSS feeds, and the subscriptions to them.
+use url::Url;
+
+pub mod gemlog;
+pub mod subscriptions;
+pub mod xml;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feed {
+    pub title: String,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub url: Url,
+    pub title: String,
+    // YYYY-MM-DD, empty when the feed doesn't say
+    pub date: String,
+    pub summary: String,
+}
+
+pub const FEED_TYPES: &[&str] = &["application/atom+xml", "application/rss+xml"];
+
+// Atom and RSS documents are recognized by their type or their first tag,
+// anything else is read as a gemtext page.
+pub fn parse(url: &Url, mime: &str, content: &str) -> Result<Feed, String> {
+    if is_xml(mime, content) {
+        xml::parse(url, content)
+    } else {
+        gemlog::parse(url, content)
+    }
+}
+
+pub fn is_xml(mime: &str, content: &str) -> bool {
+    let mime = mime.split(';').next().unwrap_or("").trim();
+    let start = content.trim_start();
+    FEED_TYPES.contains(&mime)
+        || mime == "application/xml"
+        || mime == "text/xml"
+        || start.starts_with("<?xml")
+        || start.starts_with("<rss")
+        || start.starts_with("<feed")
+}
+
+// The feed as a gemtext page: its title, then every entry with its date,
+// summary and a link to it.
+pub fn gemtext(feed: &Feed) -> String {
+    let mut content = format!("# {}\n", one_line(&feed.title));
+    if feed.entries.is_empty() {
+        content.push_str("\nThis feed has no entries.\n");
+    }
+
+    for entry in &feed.entries {
+        let title = one_line(&entry.title);
+        content.push_str(&format!("\n## {}\n", title));
+        if !entry.date.is_empty() {
+            content.push_str(&format!("{}\n", entry.date));
+        }
+        if !entry.summary.is_empty() {
+            // a summary is plain text, whatever it starts with
+            content.push_str(&format!("> {}\n", entry.summary));
+        }
+        content.push_str(&format!("=> {} {}\n", entry.url, title));
+    }
+    content
+}
+
+// A title spread over several lines would end the heading early.
+pub fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// YYYY-MM-DD
+pub fn is_date(text: &str) -> bool {
+    text.len() == 10
+        && text.bytes().enumerate().all(|(i, b)| match i {
+            4 | 7 => b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+#[test]
+fn test_gemtext() {
+    let url = Url::parse("gemini://typed-hole.org/feeds/atom.xml").unwrap();
+    let content = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Typed hole</title>
+  <entry>
+    <title>Castor 0.8</title>
+    <link href="../gemlog/castor.gmi"/>
+    <published>2020-05-17T10:00:00Z</published>
+    <content type="html">&lt;p&gt;# Not a heading&lt;/p&gt;</content>
+  </entry>
+</feed>"#;
+    let feed = parse(&url, "application/atom+xml", content).unwrap();
+
+    assert_eq!(
+        gemtext(&feed),
+        "# Typed hole
+
+## Castor 0.8
+2020-05-17
+> # Not a heading
+=> gemini://typed-hole.org/gemlog/castor.gmi Castor 0.8
+"
+    );
+}
+
+#[test]
+fn test_gemtext_title_on_several_lines() {
+    let url = Url::parse("gemini://typed-hole.org/gemlog/").unwrap();
+    let feed = Feed {
+        title: String::from("Typed\nhole"),
+        entries: vec![Entry {
+            url: url.join("castor.gmi").unwrap(),
+            title: String::from("Castor 0.8\n=> gemini://elsewhere/ released"),
+            date: String::new(),
+            summary: String::new(),
+        }],
+    };
+
+    assert_eq!(
+        gemtext(&feed),
+        "# Typed hole
+
+## Castor 0.8 => gemini://elsewhere/ released
+=> gemini://typed-hole.org/gemlog/castor.gmi Castor 0.8 => gemini://elsewhere/ released
+"
+    );
+}
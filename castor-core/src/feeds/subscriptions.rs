@@ -0,0 +1,156 @@
+// Feeds the user subscribed to, stored as gemtext links like the bookmarks,
+// and the entries already read. The last copy of every feed is kept in
+// memory and refreshed in the background.
+extern crate dirs;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::SystemTime;
+use url::Url;
+
+use super::{Entry, Feed};
+
+lazy_static! {
+    static ref FEEDS: Mutex<HashMap<Url, Result<Feed, String>>> = Mutex::new(HashMap::new());
+    static ref REFRESHED: Mutex<Option<SystemTime>> = Mutex::new(None);
+    static ref READ: Mutex<HashSet<String>> = Mutex::new(read_entries());
+}
+
+pub fn list() -> Vec<Url> {
+    fs::read_to_string(data_file("subscriptions"))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.strip_prefix("=>"))
+        .filter_map(|url| Url::parse(url.trim()).ok())
+        .collect()
+}
+
+pub fn is_subscribed(url: &Url) -> bool {
+    list().contains(url)
+}
+
+// Adds a feed returned by fetch, so only pages that turned out to be feeds
+// are added.
+pub fn subscribe(url: &Url, feed: &Feed) -> Result<(), String> {
+    if !is_subscribed(url) {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(data_file("subscriptions"))
+            .map_err(|e| e.to_string())?;
+        file.write_all(format!("=> {}\n", url).as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    FEEDS.lock().unwrap().insert(url.clone(), Ok(feed.clone()));
+    Ok(())
+}
+
+pub fn unsubscribe(url: &Url) -> Result<(), String> {
+    let content: String = list()
+        .into_iter()
+        .filter(|subscription| subscription != url)
+        .map(|subscription| format!("=> {}\n", subscription))
+        .collect();
+    fs::write(data_file("subscriptions"), content).map_err(|e| e.to_string())?;
+    FEEDS.lock().unwrap().remove(url);
+    Ok(())
+}
+
+pub fn fetch(url: &Url) -> Result<Feed, String> {
+    let (mime, content) = crate::client::fetch(url, crate::settings::max_body_size())?;
+    super::parse(url, &mime, &String::from_utf8_lossy(&content))
+}
+
+// Fetches every subscription again, one at a time.
+pub fn refresh() {
+    for url in list() {
+        let feed = fetch(&url);
+        FEEDS.lock().unwrap().insert(url, feed);
+    }
+    *REFRESHED.lock().unwrap() = Some(SystemTime::now());
+}
+
+pub fn refresh_in_background() {
+    thread::spawn(|| loop {
+        refresh();
+        thread::sleep(crate::settings::feeds_refresh_interval());
+    });
+}
+
+pub fn last_refresh() -> Option<SystemTime> {
+    *REFRESHED.lock().unwrap()
+}
+
+// Every subscription with its feed, None until it was fetched once.
+pub fn feeds() -> Vec<(Url, Option<Result<Feed, String>>)> {
+    let feeds = FEEDS.lock().unwrap();
+    list()
+        .into_iter()
+        .map(|url| {
+            let feed = feeds.get(&url).cloned();
+            (url, feed)
+        })
+        .collect()
+}
+
+// Entries of all the feeds, newest first, with the title of their feed and
+// whether they were read.
+pub fn entries() -> Vec<(String, Entry, bool)> {
+    let read = READ.lock().unwrap();
+    let mut entries: Vec<(String, Entry, bool)> = feeds()
+        .into_iter()
+        .filter_map(|(_, feed)| feed?.ok())
+        .flat_map(|feed| {
+            let title = feed.title;
+            feed.entries
+                .into_iter()
+                .map(move |entry| (title.clone(), entry))
+        })
+        .map(|(title, entry)| {
+            let is_read = read.contains(entry.url.as_str());
+            (title, entry, is_read)
+        })
+        .collect();
+    entries.sort_by(|a, b| b.1.date.cmp(&a.1.date));
+    entries
+}
+
+// Visiting an entry marks it as read, other URLs are ignored.
+pub fn mark_read(url: &Url) {
+    let is_entry = FEEDS.lock().unwrap().values().any(|feed| match feed {
+        Ok(feed) => feed.entries.iter().any(|entry| &entry.url == url),
+        Err(_) => false,
+    });
+    if !is_entry || !READ.lock().unwrap().insert(url.to_string()) {
+        return;
+    }
+
+    if let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(data_file("feeds_read"))
+    {
+        let _ = file.write_all(format!("{}\n", url).as_bytes());
+    }
+}
+
+fn read_entries() -> HashSet<String> {
+    fs::read_to_string(data_file("feeds_read"))
+        .unwrap_or_default()
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+fn data_file(name: &str) -> PathBuf {
+    let mut path = dirs::data_local_dir().unwrap_or_else(std::env::temp_dir);
+    path.push("castor");
+    let _ = fs::create_dir_all(&path);
+    path.push(name);
+    path
+}
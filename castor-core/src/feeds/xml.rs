@@ -0,0 +1,244 @@
+// Atom and RSS (0.9x, 1.0 and 2.0) feeds.
+use roxmltree::{Document, Node, ParsingOptions};
+use url::Url;
+
+use super::{is_date, Entry, Feed};
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+// Relative entry links are resolved against the URL of the feed.
+pub fn parse(url: &Url, content: &str) -> Result<Feed, String> {
+    // RSS 0.91 comes with a DOCTYPE
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let document = Document::parse_with_options(content, options)
+        .map_err(|e| format!("Invalid feed: {}", e))?;
+    let root = document.root_element();
+
+    match root.tag_name().name() {
+        "feed" => Ok(atom(url, root)),
+        "rss" | "RDF" => Ok(rss(url, root)),
+        name => Err(format!("Not a feed: <{}>", name)),
+    }
+}
+
+fn atom(url: &Url, feed: Node) -> Feed {
+    let entries = elements(feed, "entry")
+        .filter_map(|entry| {
+            let link = elements(entry, "link")
+                .find(|link| link.attribute("rel").unwrap_or("alternate") == "alternate")
+                .or_else(|| elements(entry, "link").next())
+                .and_then(|link| link.attribute("href"))?;
+            let date = text(entry, "updated").or_else(|| text(entry, "published"));
+            let summary = text(entry, "summary").or_else(|| text(entry, "content"));
+
+            Some(Entry {
+                url: url.join(link.trim()).ok()?,
+                title: text(entry, "title").unwrap_or_else(|| link.to_string()),
+                date: date.map(|date| normalize_date(&date)).unwrap_or_default(),
+                summary: summary
+                    .map(|summary| strip_html(&summary))
+                    .unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    Feed {
+        title: text(feed, "title").unwrap_or_else(|| url.to_string()),
+        entries,
+    }
+}
+
+// RSS 2.0 items are in the channel, RSS 1.0 items next to it.
+fn rss(url: &Url, root: Node) -> Feed {
+    let channel = elements(root, "channel").next();
+    let items = channel
+        .into_iter()
+        .chain(std::iter::once(root))
+        .flat_map(|parent| elements(parent, "item"));
+
+    let entries = items
+        .filter_map(|item| {
+            let link = text(item, "link").or_else(|| text(item, "guid"))?;
+            let date = text(item, "pubDate").or_else(|| text(item, "date"));
+
+            Some(Entry {
+                url: url.join(&link).ok()?,
+                title: text(item, "title").unwrap_or_else(|| link.clone()),
+                date: date.map(|date| normalize_date(&date)).unwrap_or_default(),
+                summary: text(item, "description")
+                    .map(|summary| strip_html(&summary))
+                    .unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    let title = channel.and_then(|channel| text(channel, "title"));
+    Feed {
+        title: title.unwrap_or_else(|| url.to_string()),
+        entries,
+    }
+}
+
+fn elements<'a, 'input: 'a>(
+    parent: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    parent
+        .children()
+        .filter(move |node| node.is_element() && node.tag_name().name() == name)
+}
+
+// Trimmed text of the first child element with that name, None when empty.
+fn text(parent: Node, name: &str) -> Option<String> {
+    let element = elements(parent, name).next()?;
+    let text: String = element
+        .descendants()
+        .filter(|node| node.is_text())
+        .filter_map(|node| node.text())
+        .collect();
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+// YYYY-MM-DD from an RFC 3339 (Atom) or RFC 822 (RSS) date, empty when it's
+// neither.
+pub fn normalize_date(date: &str) -> String {
+    let date = date.trim();
+    if let Some(day) = date.get(..10).filter(|day| is_date(day)) {
+        return day.to_string();
+    }
+
+    // Tue, 10 Jun 2003 04:00:00 GMT
+    let parts: Vec<&str> = date
+        .split_whitespace()
+        .filter(|part| !part.ends_with(','))
+        .collect();
+    if parts.len() < 3 {
+        return String::new();
+    }
+    let day: u32 = parts[0].parse().unwrap_or(0);
+    let month = MONTHS.iter().position(|month| {
+        parts[1]
+            .get(..3)
+            .is_some_and(|name| name.eq_ignore_ascii_case(month))
+    });
+    let year: u32 = match parts[2].parse() {
+        Ok(year) if year < 50 => 2000 + year,
+        Ok(year) if year < 100 => 1900 + year,
+        Ok(year) => year,
+        Err(_) => 0,
+    };
+
+    match month {
+        Some(month) if (1..=31).contains(&day) && year > 0 => {
+            format!("{:04}-{:02}-{:02}", year, month + 1, day)
+        }
+        _ => String::new(),
+    }
+}
+
+// Summaries often are HTML, only their text is kept.
+pub fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            c if !in_tag => text.push(c),
+            _ => (),
+        }
+    }
+
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+#[test]
+fn test_atom() {
+    let url = Url::parse("gemini://typed-hole.org/atom.xml").unwrap();
+    let content = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Typed hole</title>
+  <entry>
+    <title>Castor 0.8</title>
+    <link rel="alternate" href="/gemlog/castor.gmi"/>
+    <updated>2020-05-17T10:00:00Z</updated>
+    <summary type="html">&lt;p&gt;A new &lt;b&gt;release&lt;/b&gt;&lt;/p&gt;</summary>
+  </entry>
+  <entry>
+    <title>No link</title>
+  </entry>
+</feed>"#;
+    let feed = parse(&url, content).unwrap();
+
+    assert_eq!(feed.title, "Typed hole");
+    assert_eq!(
+        feed.entries,
+        vec![Entry {
+            url: Url::parse("gemini://typed-hole.org/gemlog/castor.gmi").unwrap(),
+            title: String::from("Castor 0.8"),
+            date: String::from("2020-05-17"),
+            summary: String::from("A new release"),
+        }]
+    );
+}
+
+#[test]
+fn test_rss() {
+    let url = Url::parse("gopher://typed-hole.org/0/rss.xml").unwrap();
+    let content = r#"<?xml version="1.0"?>
+<rss version="2.0">
+  <channel>
+    <title>Phlog</title>
+    <item>
+      <title>First</title>
+      <link>gopher://typed-hole.org/0/first.txt</link>
+      <pubDate>Sun, 17 May 2020 10:00:00 GMT</pubDate>
+      <description><![CDATA[Hello <i>gopherspace</i>]]></description>
+    </item>
+  </channel>
+</rss>"#;
+    let feed = parse(&url, content).unwrap();
+
+    assert_eq!(feed.title, "Phlog");
+    assert_eq!(feed.entries.len(), 1);
+    assert_eq!(
+        feed.entries[0].url.as_str(),
+        "gopher://typed-hole.org/0/first.txt"
+    );
+    assert_eq!(feed.entries[0].date, "2020-05-17");
+    assert_eq!(feed.entries[0].summary, "Hello gopherspace");
+
+    assert!(parse(&url, "<html></html>").is_err());
+    assert!(parse(&url, "not xml").is_err());
+}
+
+#[test]
+fn test_normalize_date() {
+    assert_eq!(normalize_date("2003-12-13T18:30:02+01:00"), "2003-12-13");
+    assert_eq!(
+        normalize_date("Tue, 10 Jun 2003 04:00:00 GMT"),
+        "2003-06-10"
+    );
+    assert_eq!(normalize_date("1 March 99"), "1999-03-01");
+    assert_eq!(normalize_date("yesterday"), "");
+}
@@ -0,0 +1,142 @@
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use url::Url;
+
+use crate::client::Stream;
+use crate::Protocol;
+
+pub fn get_stream<T: Protocol>(url: T) -> Result<(Option<Vec<u8>>, Stream), String> {
+    let path = to_path(&url.get_source_url())?;
+    let metadata = fs::metadata(&path).map_err(|e| format!("{}\n{}", path.display(), e))?;
+
+    if metadata.is_dir() {
+        let index = directory_index(&path)?;
+        Ok((Some(b"text/gemini".to_vec()), Box::new(Cursor::new(index))))
+    } else {
+        let mut file = fs::File::open(&path).map_err(|e| format!("{}\n{}", path.display(), e))?;
+        let mime = match mime_type(&path) {
+            Some(mime) => String::from(mime),
+            None => sniff(&mut file),
+        };
+        let file = fs::File::open(&path).map_err(|e| format!("{}\n{}", path.display(), e))?;
+        Ok((Some(mime.into_bytes()), Box::new(file)))
+    }
+}
+
+// Reads a whole file along with its MIME type, for uploads.
+pub fn read(path: &Path) -> Result<(String, Vec<u8>), String> {
+    let data = fs::read(path).map_err(|e| format!("{}\n{}", path.display(), e))?;
+    let mime = match mime_type(path) {
+        Some(mime) => String::from(mime),
+        None => sniff(&mut Cursor::new(&data)),
+    };
+    Ok((mime, data))
+}
+
+// Last modification of the file or directory, used to reload pages on change.
+pub fn modified(url: &Url) -> Option<SystemTime> {
+    let path = to_path(url).ok()?;
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn to_path(url: &Url) -> Result<PathBuf, String> {
+    url.to_file_path()
+        .map_err(|_| format!("Invalid file URL {}", url))
+}
+
+fn mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        "gmi" | "gemini" => Some("text/gemini"),
+        "md" | "markdown" => Some("text/markdown"),
+        "html" | "htm" => Some("text/html"),
+        "xml" | "atom" | "rss" => Some("application/xml"),
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "pdf" => Some("application/pdf"),
+        "zip" => Some("application/zip"),
+        "gz" => Some("application/gzip"),
+        _ => None,
+    }
+}
+
+// Anything that starts with valid UTF-8 and no NUL bytes is shown as text.
+fn sniff<R: Read>(file: &mut R) -> String {
+    let mut start = vec![];
+    if file.take(1024).read_to_end(&mut start).is_err() {
+        return String::from("application/octet-stream");
+    }
+
+    let text = match std::str::from_utf8(&start) {
+        Ok(text) => !text.contains('\0'),
+        // the sample may end in the middle of a character
+        Err(e) => e.error_len().is_none() && !start[..e.valid_up_to()].contains(&0),
+    };
+
+    if text {
+        String::from("text/plain")
+    } else {
+        String::from("application/octet-stream")
+    }
+}
+
+// Lists a directory as gemtext, directories first, hidden entries left out.
+fn directory_index(path: &Path) -> Result<Vec<u8>, String> {
+    let entries = fs::read_dir(path).map_err(|e| format!("{}\n{}", path.display(), e))?;
+
+    let mut entries: Vec<(bool, String, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            (!entry.path().is_dir(), name, entry.path())
+        })
+        .filter(|(_, name, _)| !name.starts_with('.'))
+        .collect();
+    entries.sort();
+
+    let mut index = format!("# Index of {}\n\n", path.display());
+    if let Some(parent) = path.parent() {
+        if let Ok(url) = Url::from_directory_path(parent) {
+            index.push_str(&format!("=> {} ..\n", url));
+        }
+    }
+    for (is_file, name, path) in entries {
+        let url = if is_file {
+            Url::from_file_path(&path)
+        } else {
+            Url::from_directory_path(&path)
+        };
+        if let Ok(url) = url {
+            let label = if is_file { name } else { format!("{}/", name) };
+            index.push_str(&format!("=> {} {}\n", url, label));
+        }
+    }
+
+    Ok(index.into_bytes())
+}
+
+#[test]
+fn test_mime_type() {
+    assert_eq!(
+        mime_type(Path::new("/capsule/index.gmi")),
+        Some("text/gemini")
+    );
+    assert_eq!(
+        mime_type(Path::new("/capsule/post.GEMINI")),
+        Some("text/gemini")
+    );
+    assert_eq!(mime_type(Path::new("/capsule/notes")), None);
+}
+
+#[test]
+fn test_sniff() {
+    assert_eq!(sniff(&mut Cursor::new("Hello ☺".as_bytes())), "text/plain");
+    assert_eq!(
+        sniff(&mut Cursor::new(b"\x7fELF\x02\x01\x01\x00")),
+        "application/octet-stream"
+    );
+}
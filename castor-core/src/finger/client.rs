@@ -0,0 +1,101 @@
+use percent_encoding::percent_decode_str;
+use std::io::Write;
+use std::net::{SocketAddr::V4, SocketAddr::V6, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use url::Url;
+
+use crate::client::Stream;
+use crate::Protocol;
+
+pub fn get_stream<T: Protocol>(url: T) -> Result<(Option<Vec<u8>>, Stream), String> {
+    let url = url.get_source_url();
+    let host = url.host_str().unwrap().to_string();
+    let port = url.port().unwrap_or(79);
+    let urlf = format!("{}:{}", host, port);
+
+    match urlf.to_socket_addrs() {
+        Ok(mut addrs_iter) => match addrs_iter.next() {
+            Some(socket_addr) => {
+                let socket_addr = match socket_addr {
+                    V4(ip) => V4(ip),
+                    V6(ip) => match addrs_iter.next() {
+                        Some(addr) => addr,
+                        None => V6(ip),
+                    },
+                };
+
+                match TcpStream::connect_timeout(&socket_addr, Duration::new(5, 0)) {
+                    Ok(mut stream) => {
+                        stream
+                            .set_read_timeout(Some(crate::settings::read_timeout()))
+                            .map_err(|e| e.to_string())?;
+                        let request = format!("{}\r\n", query(&url));
+                        stream
+                            .write_all(request.as_bytes())
+                            .map_err(|e| format!("Could not send request\n{}", e))?;
+
+                        Ok((None, Box::new(stream)))
+                    }
+                    Err(e) => Err(format!("Could not connect to {}\n{}", urlf, e)),
+                }
+            }
+            None => Err(format!("Could not connect to {}\n", urlf)),
+        },
+        Err(e) => Err(format!("Could not connect to {}\n{}", urlf, e)),
+    }
+}
+
+// Builds the RFC 1288 query from the URL:
+// finger://host lists the users, finger://user@host or finger://host/user
+// asks about one of them. A "/W" or "/w/" path asks for verbose output and
+// finger://user@host1@host2 asks host2 to forward the query to host1.
+pub fn query(url: &Url) -> String {
+    let decode = |part: &str| percent_decode_str(part).decode_utf8_lossy().to_string();
+    let path = decode(url.path().trim_start_matches('/'));
+
+    let (verbose, user) = if !url.username().is_empty() {
+        let verbose = path.eq_ignore_ascii_case("w");
+        (verbose, decode(url.username()))
+    } else if let Some(user) = strip_verbose(&path, "w/") {
+        (true, user.to_string())
+    } else if let Some(user) = strip_verbose(&path, "/w") {
+        (true, user.trim_start().to_string())
+    } else {
+        (false, path)
+    };
+
+    match (verbose, user.is_empty()) {
+        (true, true) => String::from("/W"),
+        (true, false) => format!("/W {}", user),
+        (false, _) => user,
+    }
+}
+
+fn strip_verbose<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    match path.get(..prefix.len()) {
+        Some(start) if start.eq_ignore_ascii_case(prefix) => Some(&path[prefix.len()..]),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_query() {
+    let queries = [
+        ("finger://example.org", ""),
+        ("finger://example.org/", ""),
+        ("finger://alice@example.org", "alice"),
+        ("finger://example.org/alice", "alice"),
+        ("finger://example.org/w/alice", "/W alice"),
+        ("finger://example.org/W/", "/W"),
+        ("finger://alice@example.org/W", "/W alice"),
+        ("finger://example.org/%2FW%20alice", "/W alice"),
+        ("finger://alice@host1.org@example.org", "alice@host1.org"),
+        ("finger://example.org/alice@host1.org", "alice@host1.org"),
+    ];
+
+    for (url, query_line) in queries.iter() {
+        let url = Url::parse(url).unwrap();
+        assert_eq!(query(&url), *query_line, "{}", url);
+    }
+}
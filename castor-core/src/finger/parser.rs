@@ -0,0 +1,52 @@
+use linkify::{LinkFinder, LinkKind};
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum TextElement {
+    Text(String),
+    // text with URLs in it, they are made clickable
+    Linked(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseError;
+
+impl FromStr for TextElement {
+    type Err = ParseError;
+
+    // Parses a &str into an instance of 'TextElement'
+    fn from_str(line: &str) -> Result<TextElement, ParseError> {
+        let mut finder = LinkFinder::new();
+        finder.kinds(&[LinkKind::Url]);
+
+        if finder.links(line).next().is_some() {
+            Ok(TextElement::Linked(line.to_string()))
+        } else {
+            Ok(TextElement::Text(line.to_string()))
+        }
+    }
+}
+
+pub fn parse(content: String) -> Vec<Result<TextElement, ParseError>> {
+    let mut parsed = Vec::new();
+
+    for line in content.lines() {
+        parsed.push(TextElement::from_str(line));
+    }
+    parsed
+}
+
+#[test]
+fn test_parse_links() {
+    let plan = "Plan:\nMy capsule is at gemini://example.org/ and my hole\n";
+    let parsed = parse(String::from(plan));
+
+    match &parsed[0] {
+        Ok(TextElement::Text(line)) => assert_eq!(line, "Plan:"),
+        element => panic!("unexpected element {:?}", element),
+    }
+    match &parsed[1] {
+        Ok(TextElement::Linked(line)) => assert!(line.contains("gemini://example.org/")),
+        element => panic!("unexpected element {:?}", element),
+    }
+}
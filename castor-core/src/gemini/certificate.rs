@@ -0,0 +1,107 @@
+extern crate dirs;
+use openssl::pkcs12::Pkcs12;
+use std::fs;
+use std::sync::Mutex;
+
+lazy_static! {
+    // Sent to every host instead of the certificate named after the host.
+    static ref IDENTITY: Mutex<Option<String>> = Mutex::new(None);
+}
+
+// Uses NAME.crt and NAME.key from the home directory for every request.
+pub fn set_identity(name: &str) -> Result<(), String> {
+    let home = dirs::home_dir().ok_or("No home directory")?;
+    for extension in &["crt", "key"] {
+        let path = home.join(format!("{}.{}", name, extension));
+        if !path.is_file() {
+            return Err(format!("{} not found", path.display()));
+        }
+    }
+    *IDENTITY.lock().unwrap() = Some(name.to_string());
+    Ok(())
+}
+
+pub fn get_certificate(host: &str) -> Option<Pkcs12> {
+    let name = match IDENTITY.lock().unwrap().clone() {
+        Some(identity) => identity,
+        None => host.to_string(),
+    };
+    let mut key_path = dirs::home_dir().unwrap();
+    let mut cert_path = dirs::home_dir().unwrap();
+    let key_name = format!("{}.key", name);
+    let cert_name = format!("{}.crt", name);
+
+    key_path.push(key_name);
+    cert_path.push(cert_name);
+
+    let key = match fs::read(key_path.to_str().unwrap()) {
+        Ok(file) => file,
+        Err(_) => return None,
+    };
+
+    let cert = match fs::read(cert_path.to_str().unwrap()) {
+        Ok(file) => file,
+        Err(_) => return None,
+    };
+
+    let rsa_key = openssl::rsa::Rsa::private_key_from_pem(&key).expect("Invalid RSA key");
+    let pkey = openssl::pkey::PKey::from_rsa(rsa_key).expect("Invalid PKey");
+    let cert = openssl::x509::X509::from_pem(&cert).expect("Invalid certificate");
+
+    let pkcs_cert = Pkcs12::builder()
+        .build("", "", &pkey, &cert)
+        .expect("Can't build PKCS12");
+    Some(pkcs_cert)
+}
+
+// Hosts that get a certificate, they have both a HOST.crt and a HOST.key.
+pub fn hosts() -> Vec<String> {
+    let home = match dirs::home_dir() {
+        Some(home) => home,
+        None => return vec![],
+    };
+    let entries = match fs::read_dir(&home) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut hosts: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let host = name.strip_suffix(".crt")?.to_string();
+            if home.join(format!("{}.key", host)).is_file() {
+                Some(host)
+            } else {
+                None
+            }
+        })
+        .collect();
+    hosts.sort();
+    hosts
+}
+
+// Subject, validity and fingerprint of the certificate sent to a host, as gemtext.
+pub fn describe(host: &str) -> Result<String, String> {
+    let mut cert_path = dirs::home_dir().ok_or("No home directory")?;
+    cert_path.push(format!("{}.crt", host));
+
+    let cert = fs::read(&cert_path).map_err(|e| e.to_string())?;
+    let cert = openssl::x509::X509::from_pem(&cert).map_err(|_| "Invalid certificate")?;
+
+    let mut description = String::new();
+    for entry in cert.subject_name().entries() {
+        let name = entry.object().nid().short_name().unwrap_or("?");
+        if let Ok(value) = entry.data().as_utf8() {
+            description.push_str(&format!("* {}: {}\n", name, value));
+        }
+    }
+    description.push_str(&format!("* Valid from: {}\n", cert.not_before()));
+    description.push_str(&format!("* Valid until: {}\n", cert.not_after()));
+    if let Ok(digest) = cert.digest(openssl::hash::MessageDigest::sha256()) {
+        let fingerprint: Vec<String> = digest.iter().map(|b| format!("{:02X}", b)).collect();
+        description.push_str(&format!("* SHA-256: {}\n", fingerprint.join(":")));
+    }
+    description.push_str(&format!("* File: {}\n", cert_path.display()));
+    Ok(description)
+}
@@ -1,13 +1,30 @@
-use native_tls::TlsConnector;
-use std::io::{Read, Write};
+use native_tls::{TlsConnector, TlsStream};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::net::{SocketAddr::V4, SocketAddr::V6, TcpStream, ToSocketAddrs};
-use std::thread;
 use std::time::Duration;
+use url::Url;
 
+use crate::client::Stream;
 use crate::protocols::*;
 
-pub fn get_data<T: Protocol>(url: T) -> Result<(Option<Vec<u8>>, Vec<u8>), String> {
+// <STATUS><SPACE><META><CR><LF> where META is at most 1024 bytes
+const MAX_HEADER_SIZE: u64 = 1029;
+
+pub fn get_stream<T: Protocol>(url: T) -> Result<(Option<Vec<u8>>, Stream), String> {
     let url = url.get_source_url();
+    let mut stream = connect(&url)?;
+
+    let request = format!("{}\r\n", url);
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| format!("Could not send request\n{}", e))?;
+
+    read_response(stream)
+}
+
+// Opens a TLS connection to the host of the URL, using the client certificate
+// for that host if there is one. Titan uploads go through here too.
+pub fn connect(url: &Url) -> Result<TlsStream<TcpStream>, String> {
     let host = url.host_str().unwrap_or("");
     let port = url.port().unwrap_or(1965);
     let urlf = format!("{}:{}", host, port);
@@ -39,29 +56,12 @@ pub fn get_data<T: Protocol>(url: T) -> Result<(Option<Vec<u8>>, Vec<u8>), Strin
 
                 match stream {
                     Ok(stream) => {
-                        let mstream = connector.connect(&host, stream);
-
-                        match mstream {
-                            Ok(mut stream) => thread::spawn(move || {
-                                let url = format!("{}\r\n", url);
-                                stream.write_all(url.as_bytes()).unwrap();
-                                let mut res = vec![];
-                                stream.read_to_end(&mut res).unwrap();
-
-                                let crlf_idx = find_crlf(&res);
-
-                                if crlf_idx.is_none() {
-                                    return Err("Failed to read response (missing crlf)".to_string());
-                                }
-
-                                let content = res.split_off(crlf_idx.unwrap() + 2);
-
-                                Ok((Some(res), content))
-                            })
-                            .join()
-                            .unwrap(),
-                            Err(e) => Err(format!("Could not connect to {}\n{}", urlf, e)),
-                        }
+                        stream
+                            .set_read_timeout(Some(crate::settings::read_timeout()))
+                            .map_err(|e| e.to_string())?;
+                        connector
+                            .connect(&host, stream)
+                            .map_err(|e| format!("Could not connect to {}\n{}", urlf, e))
                     }
                     Err(e) => Err(format!("Could not connect to {}\n{}", urlf, e)),
                 }
@@ -72,6 +72,25 @@ pub fn get_data<T: Protocol>(url: T) -> Result<(Option<Vec<u8>>, Vec<u8>), Strin
     }
 }
 
+// Splits the response header from the body that follows it.
+pub fn read_response<S: Read + Send + 'static>(
+    stream: S,
+) -> Result<(Option<Vec<u8>>, Stream), String> {
+    let mut reader = BufReader::new(stream);
+    let mut meta = vec![];
+    reader
+        .by_ref()
+        .take(MAX_HEADER_SIZE)
+        .read_until(b'\n', &mut meta)
+        .map_err(|e| format!("Failed to read response\n{}", e))?;
+
+    if find_crlf(&meta).is_none() {
+        return Err("Failed to read response (missing crlf)".to_string());
+    }
+
+    Ok((Some(meta), Box::new(reader)))
+}
+
 fn find_crlf(data: &[u8]) -> Option<usize> {
     let crlf = b"\r\n";
     data.windows(crlf.len()).position(|window| window == crlf)
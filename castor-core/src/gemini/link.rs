@@ -5,10 +5,14 @@ use url::Url;
 
 #[derive(Debug)]
 pub enum Link {
+    About(Url, String),
+    File(Url, String),
     Finger(Url, String),
     Gemini(Url, String),
     Gopher(Url, String),
     Http(Url, String),
+    Nex(Url, String),
+    Spartan(Url, String),
     Email(Url, String),
     IRC(Url, String),
     XMPP(Url, String),
@@ -54,11 +58,15 @@ fn make_link(url: String, label: String) -> Option<Link> {
     let urlp = Url::parse(&url);
     match urlp {
         Ok(url) => match url.scheme() {
+            "about" => Some(Link::About(url, label)),
+            "file" => Some(Link::File(url, label)),
             "finger" => Some(Link::Finger(url, label)),
             "gemini" => Some(Link::Gemini(url, label)),
             "gopher" => Some(Link::Gopher(url, label)),
             "http" => Some(Link::Http(url, label)),
             "https" => Some(Link::Http(url, label)),
+            "nex" => Some(Link::Nex(url, label)),
+            "spartan" => Some(Link::Spartan(url, label)),
             "mailto" => Some(Link::Email(url, label)),
             "xmpp" => Some(Link::XMPP(url, label)),
             "irc" => Some(Link::IRC(url, label)),
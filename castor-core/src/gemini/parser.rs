@@ -7,6 +7,7 @@ pub enum TextElement {
     H3(String),
     ListItem(String),
     LinkItem(String),
+    PromptItem(String),
     Text(String),
     MonoText(String),
     Quote(String),
@@ -54,3 +55,35 @@ pub fn parse(content: String) -> Vec<Result<TextElement, ParseError>> {
     }
     parsed
 }
+
+// `=:` prompt lines only exist on Spartan pages, other pages keep them as text.
+pub fn parse_spartan(content: String) -> Vec<Result<TextElement, ParseError>> {
+    let mut parsed = Vec::new();
+
+    for line in content.lines() {
+        parsed.push(spartan_line(line));
+    }
+    parsed
+}
+
+pub fn spartan_line(line: &str) -> Result<TextElement, ParseError> {
+    if line.starts_with("=:") {
+        Ok(TextElement::PromptItem(String::from(line)))
+    } else {
+        TextElement::from_str(line)
+    }
+}
+
+#[test]
+fn test_prompt_only_on_spartan() {
+    let content = String::from("=: /search Search");
+
+    assert!(matches!(
+        parse(content.clone()).as_slice(),
+        [Ok(TextElement::Text(line))] if line == "=: /search Search"
+    ));
+    assert!(matches!(
+        parse_spartan(content).as_slice(),
+        [Ok(TextElement::PromptItem(_))]
+    ));
+}
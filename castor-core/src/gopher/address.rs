@@ -0,0 +1,185 @@
+// Gopher URLs as described in RFC 4266:
+// gopher://host:port/<type><selector>%09<search>%09<gopher+ string>
+// where the selector, search and Gopher+ string are percent-encoded.
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use url::Url;
+
+// Characters that can't appear as is in the path of a URL.
+const SELECTOR: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+#[derive(Debug, PartialEq)]
+pub struct Address {
+    pub item_type: char,
+    pub selector: String,
+    pub search: Option<String>,
+    pub plus: Option<String>,
+}
+
+impl Address {
+    // The line sent to the server.
+    pub fn request(&self) -> String {
+        let mut request = self.selector.clone();
+        if let Some(search) = &self.search {
+            if !search.is_empty() {
+                request.push('\t');
+                request.push_str(search);
+            }
+        }
+        if let Some(plus) = &self.plus {
+            request.push('\t');
+            request.push_str(plus);
+        }
+        request.push_str("\r\n");
+        request
+    }
+}
+
+// Builds the URL of a menu item, the selector is kept as the server sent it.
+pub fn to_url(host: &str, port: &str, item_type: char, selector: &str) -> Option<Url> {
+    let url = format!(
+        "gopher://{}:{}/{}{}",
+        host,
+        port,
+        item_type,
+        utf8_percent_encode(selector, SELECTOR)
+    );
+    Url::parse(&url).ok()
+}
+
+// Same URL with search terms, sent after the selector.
+pub fn with_search(url: &Url, search: &str) -> Option<Url> {
+    let url = format!(
+        "{}%09{}",
+        url.as_str().trim_end_matches('?'),
+        utf8_percent_encode(search, SELECTOR)
+    );
+    Url::parse(&url).ok()
+}
+
+// Same URL with a Gopher+ string, sent after the selector and search terms.
+pub fn with_plus(url: &Url, plus: &str) -> Option<Url> {
+    let address = parse(url);
+    let search = address.search.unwrap_or_default();
+    let url = format!(
+        "{}://{}/{}{}%09{}%09{}",
+        url.scheme(),
+        host_port(url),
+        address.item_type,
+        utf8_percent_encode(&address.selector, SELECTOR),
+        utf8_percent_encode(&search, SELECTOR),
+        utf8_percent_encode(plus, SELECTOR)
+    );
+    Url::parse(&url).ok()
+}
+
+pub fn parse(url: &Url) -> Address {
+    // a "?" typed in the URL bar is part of the selector
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+
+    let mut chars = path.trim_start_matches('/').chars();
+    let item_type = chars.next().unwrap_or('1');
+    let rest = chars.as_str();
+
+    let mut parts = rest.splitn(3, "%09");
+    let selector = decode(parts.next().unwrap_or(""));
+    let search = parts.next().map(decode);
+    let plus = parts.next().map(decode);
+
+    Address {
+        item_type,
+        selector,
+        search,
+        plus,
+    }
+}
+
+fn decode(part: &str) -> String {
+    percent_decode_str(part).decode_utf8_lossy().to_string()
+}
+
+fn host_port(url: &Url) -> String {
+    let host = url.host_str().unwrap_or("");
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    let selectors = [
+        "/docs/about.txt",
+        "about.txt",
+        "/files/my notes 100%.txt",
+        "/search?q=castor#top",
+        "/café/menu",
+        "",
+    ];
+
+    for selector in selectors.iter() {
+        let url = to_url("gopher.example.org", "70", '0', selector).unwrap();
+        let address = parse(&url);
+        assert_eq!(address.item_type, '0');
+        assert_eq!(address.selector, *selector);
+        assert_eq!(address.search, None);
+        assert_eq!(address.request(), format!("{}\r\n", selector));
+    }
+}
+
+#[test]
+fn test_encoded_url() {
+    let url = to_url("gopher.example.org", "70", '1', "/my files").unwrap();
+    assert_eq!(url.as_str(), "gopher://gopher.example.org:70/1/my%20files");
+
+    let url = to_url("gopher.example.org", "70", '0', "relative").unwrap();
+    assert_eq!(url.as_str(), "gopher://gopher.example.org:70/0relative");
+}
+
+#[test]
+fn test_search_and_plus() {
+    let url = to_url("gopher.example.org", "70", '7', "/v2/vs").unwrap();
+    let search = with_search(&url, "gemini clients").unwrap();
+    assert_eq!(
+        search.as_str(),
+        "gopher://gopher.example.org:70/7/v2/vs%09gemini%20clients"
+    );
+    let address = parse(&search);
+    assert_eq!(address.selector, "/v2/vs");
+    assert_eq!(address.search.as_deref(), Some("gemini clients"));
+    assert_eq!(address.request(), "/v2/vs\tgemini clients\r\n");
+
+    let info = with_plus(&url, "!").unwrap();
+    let address = parse(&info);
+    assert_eq!(address.plus.as_deref(), Some("!"));
+    assert_eq!(address.request(), "/v2/vs\t!\r\n");
+
+    let view = with_plus(&search, "+text/plain En_US").unwrap();
+    assert_eq!(
+        parse(&view).request(),
+        "/v2/vs\tgemini clients\t+text/plain En_US\r\n"
+    );
+}
+
+#[test]
+fn test_default_item() {
+    let url = Url::parse("gopher://gopher.example.org").unwrap();
+    let address = parse(&url);
+    assert_eq!(address.item_type, '1');
+    assert_eq!(address.request(), "\r\n");
+
+    let url = Url::parse("gopher://gopher.example.org/0/notes?draft").unwrap();
+    assert_eq!(parse(&url).selector, "/notes?draft");
+}
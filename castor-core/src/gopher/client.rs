@@ -0,0 +1,127 @@
+use native_tls::TlsConnector;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr::V4, SocketAddr::V6, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use url::Url;
+
+use crate::client::Stream;
+use crate::Protocol;
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn get_stream<T: Protocol>(url: T) -> Result<(Option<Vec<u8>>, Stream), String> {
+    get_secure_stream(url).map(|(_secure, stream)| (None, stream))
+}
+
+// gophers:// URLs always use TLS, hosts listed in the settings try it first
+// and fall back to plain text. Also tells whether TLS was used.
+pub fn get_secure_stream<T: Protocol>(url: T) -> Result<(bool, Stream), String> {
+    let url = url.get_source_url();
+    let host = url.host_str().unwrap().to_string();
+    let port = url.port().unwrap_or(70);
+    let urlf = format!("{}:{}", host, port);
+    let (selector, plus) = request(&url);
+
+    if url.scheme() == "gophers" || crate::settings::gopher_try_tls(&host) {
+        // a plain text server never answers the handshake, don't wait long
+        let stream = connect(&urlf)?;
+        stream
+            .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+            .map_err(|e| e.to_string())?;
+
+        match connect_tls(&host, stream) {
+            Ok(mut stream) => {
+                // without a certificate there is nothing to check the pin against
+                let certificate = stream
+                    .peer_certificate()
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| format!("{} sent no certificate", urlf))?;
+                let der = certificate.to_der().map_err(|e| e.to_string())?;
+                crate::known_hosts::check(&urlf, &der)?;
+                stream
+                    .get_ref()
+                    .set_read_timeout(Some(crate::settings::read_timeout()))
+                    .map_err(|e| e.to_string())?;
+                stream
+                    .write_all(selector.as_bytes())
+                    .map_err(|e| format!("Could not send request\n{}", e))?;
+
+                return Ok((true, body(Box::new(stream), plus)?));
+            }
+            Err(e) if url.scheme() == "gophers" => return Err(e),
+            Err(_) => (),
+        }
+    }
+
+    let mut stream = connect(&urlf)?;
+    stream
+        .write_all(selector.as_bytes())
+        .map_err(|e| format!("Could not send request\n{}", e))?;
+
+    Ok((false, body(Box::new(stream), plus)?))
+}
+
+fn connect(urlf: &str) -> Result<TcpStream, String> {
+    match urlf.to_socket_addrs() {
+        Ok(mut addrs_iter) => match addrs_iter.next() {
+            Some(socket_addr) => {
+                let socket_addr = match socket_addr {
+                    V4(ip) => V4(ip),
+                    V6(ip) => match addrs_iter.next() {
+                        Some(addr) => addr,
+                        None => V6(ip),
+                    },
+                };
+
+                let stream = TcpStream::connect_timeout(&socket_addr, Duration::new(5, 0))
+                    .map_err(|e| format!("Could not connect to {}\n{}", urlf, e))?;
+                stream
+                    .set_read_timeout(Some(crate::settings::read_timeout()))
+                    .map_err(|e| e.to_string())?;
+                Ok(stream)
+            }
+            None => Err(format!("Could not connect to {}\n", urlf)),
+        },
+        Err(e) => Err(format!("Could not connect to {}\n{}", urlf, e)),
+    }
+}
+
+// Certificates are checked against the known hosts instead of a CA.
+fn connect_tls(host: &str, stream: TcpStream) -> Result<native_tls::TlsStream<TcpStream>, String> {
+    let mut builder = TlsConnector::builder();
+    builder.danger_accept_invalid_hostnames(true);
+    builder.danger_accept_invalid_certs(true);
+    let connector = builder.build().map_err(|e| e.to_string())?;
+
+    connector
+        .connect(host, stream)
+        .map_err(|e| format!("Could not establish a TLS connection to {}\n{}", host, e))
+}
+
+// The request line is the selector, followed by the search terms and the
+// Gopher+ string when the URL has them. Also tells whether it's a Gopher+ request.
+fn request(url: &Url) -> (String, bool) {
+    let address = super::address::parse(url);
+    (address.request(), address.plus.is_some())
+}
+
+// Gopher+ responses start with a "+<length>" line, or "-<length>" followed
+// by an error message.
+fn body(stream: Stream, plus: bool) -> Result<Stream, String> {
+    if !plus {
+        return Ok(stream);
+    }
+
+    let mut reader = BufReader::new(stream);
+    let mut header = String::new();
+    reader
+        .read_line(&mut header)
+        .map_err(|e| format!("Failed to read response\n{}", e))?;
+
+    if header.starts_with('-') {
+        let mut message = String::new();
+        reader.read_to_string(&mut message).ok();
+        return Err(format!("The server returned an error\n{}", message.trim()));
+    }
+    Ok(Box::new(reader))
+}
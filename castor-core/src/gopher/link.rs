@@ -0,0 +1,266 @@
+extern crate linkify;
+use linkify::LinkFinder;
+
+use std::str::FromStr;
+use url::Url;
+
+use super::address;
+
+#[derive(Debug)]
+pub enum Link {
+    File(Url, String, &'static str),
+    Ftp(Url, String),
+    Finger(Url, String),
+    Gemini(Url, String),
+    Gopher(Url, String),
+    Http(Url, String),
+    Image(Url, String),
+    Search(Url, String),
+    Telnet(Url, String),
+    Relative(String, String),
+    Unknown(Url, String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseError;
+
+impl FromStr for Link {
+    type Err = ParseError;
+
+    // Parses a &str into an instance of 'Link'
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut els = line.split('\t');
+
+        if line.starts_with('0') || line.starts_with('1') {
+            match menu_item(line) {
+                Some((url, text)) => Ok(Link::Gopher(url, text)),
+                None => Err(ParseError),
+            }
+        } else if line.starts_with('g') || line.starts_with('I') {
+            match menu_item(line) {
+                Some((url, text)) => Ok(Link::Image(url, text)),
+                None => Err(ParseError),
+            }
+        } else if let Some(kind) = download_kind(line) {
+            match menu_item(line) {
+                Some((url, text)) => Ok(Link::File(url, text, kind)),
+                None => Err(ParseError),
+            }
+        } else if line.contains('\t') && (line.starts_with('8') || line.starts_with('T')) {
+            let label = els.next().unwrap_or("");
+            let login = els.next().unwrap_or("");
+            let host = els.next();
+            let port = els.next();
+
+            if let (Some(host), Some(port)) = (host, port) {
+                let scheme = if line.starts_with('T') {
+                    "tn3270"
+                } else {
+                    "telnet"
+                };
+                let login = if login.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}@", login)
+                };
+
+                match Url::parse(&format!("{}://{}{}:{}", scheme, login, host, port)) {
+                    Ok(url) => Ok(Link::Telnet(url, label.chars().skip(1).collect())),
+                    Err(_) => Err(ParseError),
+                }
+            } else {
+                Err(ParseError)
+            }
+        } else if line.starts_with('7') {
+            match menu_item(line) {
+                Some((url, text)) => Ok(Link::Search(url, text)),
+                None => Err(ParseError),
+            }
+        } else if line.starts_with('[') {
+            let url = extract_url(line);
+            let label = String::from(line);
+
+            match make_link(String::from(url), label) {
+                Some(link) => Ok(link),
+                None => Err(ParseError),
+            }
+        } else if line.starts_with('h') {
+            let label = els.next();
+            let url = els.next();
+
+            // the selector is "URL:" followed by the URL
+            match (label, url.and_then(|url| url.get(4..))) {
+                (Some(label), Some(url)) => {
+                    match make_link(String::from(url), label.chars().skip(1).collect()) {
+                        Some(link) => Ok(link),
+                        None => Err(ParseError),
+                    }
+                }
+                _ => Err(ParseError),
+            }
+        } else if line.contains("://") {
+            let url = extract_url(line);
+            let label = String::from(line);
+
+            match make_link(String::from(url), label) {
+                Some(link) => Ok(link),
+                None => Err(ParseError),
+            }
+        } else {
+            Err(ParseError)
+        }
+    }
+}
+
+pub fn make_link(url: String, label: String) -> Option<Link> {
+    match Url::parse(&url) {
+        Ok(url) => match url.scheme() {
+            "finger" => Some(Link::Finger(url, label)),
+            "ftp" => Some(Link::Ftp(url, label)),
+            "gemini" => Some(Link::Gemini(url, label)),
+            "gopher" => Some(Link::Gopher(url, label)),
+            "http" => Some(Link::Http(url, label)),
+            "https" => Some(Link::Http(url, label)),
+            _ => Some(Link::Unknown(url, label)),
+        },
+        Err(url::ParseError::RelativeUrlWithoutBase) => Some(Link::Relative(url, label)),
+        _ => None,
+    }
+}
+
+// Binary items are downloaded, the kind is shown next to their label.
+fn download_kind(line: &str) -> Option<&'static str> {
+    if !line.contains('\t') {
+        return None;
+    }
+
+    match line.chars().next() {
+        Some('9') => Some("File"),
+        Some('5') => Some("Archive"),
+        Some('s') => Some("Sound"),
+        Some(';') => Some("Video"),
+        Some('d') => Some("Document"),
+        Some('p') => Some("PDF"),
+        Some('c') => Some("Calendar"),
+        _ => None,
+    }
+}
+
+// Builds the URL of a menu item from its type, selector, host and port.
+fn menu_item(line: &str) -> Option<(Url, String)> {
+    let mut els = line.split('\t');
+    let mut text = els.next()?.chars();
+    let selector = els.next()?;
+    let host = els.next()?;
+    let port = els.next()?;
+
+    let item_type = text.next()?;
+    let url = address::to_url(host, port.trim(), item_type, selector)?;
+    Some((url, text.collect()))
+}
+
+fn extract_url(line: &str) -> &str {
+    let finder = LinkFinder::new();
+    let links: Vec<_> = finder.links(line).collect();
+    if links.is_empty() {
+        line
+    } else {
+        let link = &links[0];
+        link.as_str()
+    }
+}
+
+#[test]
+fn test_search_item() {
+    let line = "7Search Veronica-2\t/v2/vs\tgopher.floodgap.com\t70";
+    match Link::from_str(line) {
+        Ok(Link::Search(url, label)) => {
+            assert_eq!(url.as_str(), "gopher://gopher.floodgap.com:70/7/v2/vs");
+            assert_eq!(label, "Search Veronica-2");
+        }
+        link => panic!("unexpected link {:?}", link),
+    }
+}
+
+#[test]
+fn test_download_items() {
+    let items = [
+        (
+            "5Source tarball\t/src/castor.tar.gz\tgopher.example.org\t70",
+            "Archive",
+        ),
+        (
+            "sEpisode 1\t/audio/ep1.mp3\tgopher.example.org\t70",
+            "Sound",
+        ),
+        (
+            ";Conference talk\t/video/talk.mp4\tgopher.example.org\t70",
+            "Video",
+        ),
+        (
+            "dManual\tdocs/manual.doc\tgopher.example.org\t70",
+            "Document",
+        ),
+        (
+            "pRFC 1436\t/docs/rfc1436.pdf\tgopher.example.org\t70",
+            "PDF",
+        ),
+        ("cMeetups\t/events.ics\tgopher.example.org\t70", "Calendar"),
+    ];
+
+    for (line, expected_kind) in items.iter() {
+        match Link::from_str(line) {
+            Ok(Link::File(url, _, kind)) => {
+                assert_eq!(kind, *expected_kind);
+                assert!(url.as_str().starts_with("gopher://gopher.example.org:70/"));
+            }
+            link => panic!("unexpected link {:?}", link),
+        }
+    }
+}
+
+#[test]
+fn test_telnet_items() {
+    match Link::from_str("8SDF BBS\tnew\tsdf.org\t23") {
+        Ok(Link::Telnet(url, label)) => {
+            assert_eq!(url.as_str(), "telnet://new@sdf.org:23");
+            assert_eq!(label, "SDF BBS");
+        }
+        link => panic!("unexpected link {:?}", link),
+    }
+    match Link::from_str("TIBM mainframe\t\tibm.example.org\t23") {
+        Ok(Link::Telnet(url, _)) => assert_eq!(url.as_str(), "tn3270://ibm.example.org:23"),
+        link => panic!("unexpected link {:?}", link),
+    }
+}
+
+#[test]
+fn test_selector_encoding() {
+    let line = "0Notes 100% done\t/my notes/100%.txt\tgopher.example.org\t70";
+    match Link::from_str(line) {
+        Ok(Link::Gopher(url, label)) => {
+            assert_eq!(
+                url.as_str(),
+                "gopher://gopher.example.org:70/0/my%20notes/100%25.txt"
+            );
+            assert_eq!(label, "Notes 100% done");
+            assert_eq!(address::parse(&url).selector, "/my notes/100%.txt");
+        }
+        link => panic!("unexpected link {:?}", link),
+    }
+}
+
+#[test]
+fn test_malformed_items() {
+    for line in &["hWeb\tx", "h", "8\t", "1\t\t\t", "0é\t/\t\t"] {
+        assert!(Link::from_str(line).is_err(), "{:?}", line);
+    }
+
+    match Link::from_str("hWeb\tURL:https://example.org\texample.org\t70") {
+        Ok(Link::Http(url, label)) => {
+            assert_eq!(url.as_str(), "https://example.org/");
+            assert_eq!(label, "Web");
+        }
+        link => panic!("unexpected link {:?}", link),
+    }
+}
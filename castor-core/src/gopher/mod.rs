@@ -1,3 +1,5 @@
+pub mod address;
 pub mod client;
 pub mod link;
 pub mod parser;
+pub mod plus;
@@ -0,0 +1,139 @@
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum TextElement {
+    Binary(String),
+    Error(String),
+    ExternalLinkItem(String),
+    LinkItem(String),
+    Image(String),
+    Search(String),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseError;
+
+impl FromStr for TextElement {
+    type Err = ParseError;
+
+    // Parses a &str into an instance of 'TextElement'
+    fn from_str(line: &str) -> Result<TextElement, ParseError> {
+        let columns = line.split('\t').count();
+        if columns >= 2 {
+            // Gophermap line
+            if line.starts_with('0') || line.starts_with('1') {
+                Ok(TextElement::LinkItem(String::from(line)))
+            } else if line.starts_with('i') {
+                // leading spaces are kept, lining info lines up with the
+                // links around them is done by the grid layout
+                let label = line.split('\t').next().unwrap_or("");
+                Ok(TextElement::Text(label.chars().skip(1).collect()))
+            } else if line.starts_with('h') {
+                Ok(TextElement::ExternalLinkItem(String::from(line)))
+            } else if line.starts_with('I') || line.starts_with('g') {
+                Ok(TextElement::Image(String::from(line)))
+            } else if line.starts_with('9') {
+                Ok(TextElement::Binary(String::from(line)))
+            } else if line.starts_with('7') {
+                Ok(TextElement::Search(String::from(line)))
+            } else if columns >= 4 && line.starts_with('3') {
+                let label = line.split('\t').next().unwrap_or("");
+                Ok(TextElement::Error(label.chars().skip(1).collect()))
+            } else if columns >= 4 && (line.starts_with('8') || line.starts_with('T')) {
+                Ok(TextElement::ExternalLinkItem(String::from(line)))
+            } else if columns >= 4 && line.starts_with(&['5', 's', ';', 'd', 'p', 'c'][..]) {
+                Ok(TextElement::Binary(String::from(line)))
+            } else {
+                Ok(TextElement::Text(String::from(line)))
+            }
+        } else {
+            // Text line
+            if line.contains("gopher://") {
+                Ok(TextElement::LinkItem(String::from(line)))
+            } else if line.contains("http://")
+                || line.contains("https://")
+                || line.contains("gemini://")
+                || line.contains("finger://")
+                || line.contains("ftp://")
+            {
+                Ok(TextElement::ExternalLinkItem(String::from(line)))
+            } else {
+                Ok(TextElement::Text(String::from(line)))
+            }
+        }
+    }
+}
+
+pub fn parse(content: String) -> Vec<Result<TextElement, ParseError>> {
+    let mut parsed = Vec::new();
+    let mut previous_type = '1';
+
+    for line in content.lines() {
+        if line.starts_with('+') && line.contains('\t') {
+            // redundant server, same kind of item as the one above
+            let mirror = format!("{}{}", previous_type, &line[1..]);
+            parsed.push(TextElement::from_str(&mirror));
+        } else {
+            if line.contains('\t') {
+                previous_type = line.chars().next().unwrap_or(previous_type);
+            }
+            parsed.push(TextElement::from_str(line));
+        }
+    }
+    parsed
+}
+
+// Hand-written, one line per item type. Lines captured from live servers
+// should replace them.
+#[cfg(test)]
+const MENU: &str = "iWelcome to Floodgap Systems' official gopher server.\t\terror.host\t1
+1Floodgap Systems gopher root\t/\tgopher.floodgap.com\t70
++Floodgap Systems gopher root\t/\tgopher2.floodgap.com\t70
+0About this server\t/gopher/welcome\tgopher.floodgap.com\t70
+7Search Veronica-2\t/v2/vs\tgopher.floodgap.com\t70
+hSDF on the web\tURL:https://sdf.org\tsdf.org\t70
+IFloodgap logo\t/gopher/logo.gif\tgopher.floodgap.com\t70
+9Lynx for DOS\t/archive/lynx.exe\tgopher.floodgap.com\t70
+5Source tarball\t/src/castor.tar.gz\tgopher.example.org\t70
+sEpisode 1\t/audio/ep1.mp3\tgopher.example.org\t70
+;Conference talk\t/video/talk.mp4\tgopher.example.org\t70
+dManual\t/docs/manual.doc\tgopher.example.org\t70
+pRFC 1436\t/docs/rfc1436.pdf\tgopher.example.org\t70
+cMeetups\t/events.ics\tgopher.example.org\t70
+8SDF BBS\tnew\tsdf.org\t23
+TIBM mainframe\t\tibm.example.org\t23
+3'/nope' doesn't exist!\t\terror.host\t1
+Plain text\twith a tab";
+
+#[test]
+fn test_parse_menu() {
+    let kinds: Vec<&str> = parse(String::from(MENU))
+        .iter()
+        .map(|element| match element {
+            Ok(TextElement::Binary(_)) => "binary",
+            Ok(TextElement::Error(_)) => "error",
+            Ok(TextElement::ExternalLinkItem(_)) => "external",
+            Ok(TextElement::LinkItem(_)) => "link",
+            Ok(TextElement::Image(_)) => "image",
+            Ok(TextElement::Search(_)) => "search",
+            Ok(TextElement::Text(_)) => "text",
+            Err(_) => "error",
+        })
+        .collect();
+
+    assert_eq!(
+        kinds,
+        vec![
+            "text", "link", "link", "link", "search", "external", "image", "binary", "binary",
+            "binary", "binary", "binary", "binary", "binary", "external", "external", "error",
+            "text",
+        ]
+    );
+}
+
+#[test]
+fn test_parse_info_line_spaces() {
+    let parsed = parse(String::from("i   |  Castor  |\t\terror.host\t1\n"));
+    assert!(matches!(&parsed[0], Ok(TextElement::Text(text)) if text == "   |  Castor  |"));
+}
@@ -0,0 +1,137 @@
+// Gopher+ items have a fifth column starting with "+" (or "?" when they
+// also ask for input). Their attributes are listed by sending "selector\t!".
+
+#[derive(Debug, PartialEq)]
+pub struct Attribute {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct View {
+    pub mime: String,
+    pub language: String,
+    pub size: String,
+}
+
+impl View {
+    // What to send after the selector to get this view.
+    pub fn request(&self) -> String {
+        if self.language.is_empty() {
+            format!("+{}", self.mime)
+        } else {
+            format!("+{} {}", self.mime, self.language)
+        }
+    }
+
+    pub fn label(&self) -> String {
+        let mut label = self.mime.clone();
+        if !self.language.is_empty() {
+            label.push_str(&format!(" ({})", self.language));
+        }
+        if !self.size.is_empty() {
+            label.push_str(&format!(", {}", self.size));
+        }
+        label
+    }
+}
+
+pub fn is_gopher_plus(line: &str) -> bool {
+    match line.split('\t').nth(4) {
+        Some(plus) => plus.starts_with('+') || plus.starts_with('?'),
+        None => false,
+    }
+}
+
+// Parses the "+NAME: value" blocks of an attribute response, continuation
+// lines start with a space.
+pub fn parse_attributes(content: &str) -> Vec<Attribute> {
+    let mut attributes: Vec<Attribute> = vec![];
+
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        if line == "." {
+            break;
+        } else if line.starts_with('+') && line.contains(':') {
+            let (name, value) = line.split_at(line.find(':').unwrap());
+            attributes.push(Attribute {
+                name: name[1..].to_string(),
+                value: value[1..].trim().to_string(),
+            });
+        } else if let Some(attribute) = attributes.last_mut() {
+            if !attribute.value.is_empty() {
+                attribute.value.push('\n');
+            }
+            attribute.value.push_str(line.trim());
+        }
+    }
+    attributes
+}
+
+// "+VIEWS" lines look like "text/plain: <10k>" or "application/pdf En_US: <2M>".
+pub fn views(attributes: &[Attribute]) -> Vec<View> {
+    attributes
+        .iter()
+        .filter(|attribute| attribute.name == "VIEWS")
+        .flat_map(|attribute| attribute.value.lines())
+        .filter_map(|line| {
+            let colon = line.find(':')?;
+            let mut kind = line[..colon].split_whitespace();
+            let mime = kind.next()?.to_string();
+            let language = kind.next().unwrap_or("").to_string();
+            let size = line[colon + 1..]
+                .trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string();
+
+            Some(View {
+                mime,
+                language,
+                size,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+const ATTRIBUTES: &str = "+INFO: 0About Floodgap\t/gopher/about\tgopher.floodgap.com\t70\t+\r
++ADMIN:\r
+ Admin: Cameron Kaiser <ckaiser@floodgap.com>\r
+ Mod-Date: Sun Mar 14 10:00:00 2021 <20210314100000>\r
++VIEWS:\r
+ text/plain: <3k>\r
+ application/pdf En_US: <120k>\r
++ABSTRACT:\r
+ What this server is about.\r
+.\r
+";
+
+#[test]
+fn test_is_gopher_plus() {
+    assert!(is_gopher_plus(
+        "0About Floodgap\t/gopher/about\tgopher.floodgap.com\t70\t+"
+    ));
+    assert!(!is_gopher_plus(
+        "0About Floodgap\t/gopher/about\tgopher.floodgap.com\t70"
+    ));
+}
+
+#[test]
+fn test_parse_attributes() {
+    let attributes = parse_attributes(ATTRIBUTES);
+    let names: Vec<&str> = attributes.iter().map(|a| a.name.as_str()).collect();
+
+    assert_eq!(names, vec!["INFO", "ADMIN", "VIEWS", "ABSTRACT"]);
+    assert_eq!(attributes[3].value, "What this server is about.");
+}
+
+#[test]
+fn test_views() {
+    let views = views(&parse_attributes(ATTRIBUTES));
+
+    assert_eq!(views.len(), 2);
+    assert_eq!(views[0].request(), "+text/plain");
+    assert_eq!(views[1].request(), "+application/pdf En_US");
+    assert_eq!(views[1].label(), "application/pdf (En_US), 120k");
+}
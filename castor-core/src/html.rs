@@ -0,0 +1,568 @@
+// A minimal HTML reader: markup is stripped and the page is turned into
+// gemtext, keeping headings, paragraphs, lists, quotes, preformatted
+// blocks and links. Links are listed after the block they appear in.
+use url::Url;
+
+// Elements whose content is never shown.
+const HIDDEN: &[&str] = &["head", "script", "style", "svg", "template"];
+
+const BLOCKS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "header",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Block {
+    Paragraph,
+    Heading(usize),
+    Item,
+}
+
+struct Page<'a> {
+    url: &'a Url,
+    lines: Vec<String>,
+    block: Block,
+    last: Option<Block>,
+    text: String,
+    links: Vec<(String, String)>,
+    link: Option<(String, String)>,
+    quote: usize,
+    pre: Option<String>,
+}
+
+pub fn gemtext(url: &Url, html: &str) -> String {
+    let mut page = Page {
+        url,
+        lines: vec![],
+        block: Block::Paragraph,
+        last: None,
+        text: String::new(),
+        links: vec![],
+        link: None,
+        quote: 0,
+        pre: None,
+    };
+    let mut title = String::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        page.text(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+
+        let (name, closing, tag, after) = match tag(rest) {
+            Some(parsed) => parsed,
+            None => {
+                // a lone "<" is just text
+                page.text("<");
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        rest = after;
+
+        if !closing && (name == "title" || HIDDEN.contains(&name.as_str())) {
+            let end = find_closing(rest, &name);
+            if name == "title" {
+                title = collapse(&decode(&rest[..end]));
+            }
+            rest = rest[end..].find('>').map_or("", |i| &rest[end + i + 1..]);
+            continue;
+        }
+        page.tag(&name, closing, tag);
+    }
+    page.text(rest);
+    page.flush();
+    if let Some(pre) = page.pre.take() {
+        page.preformatted(&pre);
+    }
+
+    let mut lines = page.lines;
+    if !title.is_empty() && !lines.iter().any(|line| line.starts_with("# ")) {
+        lines.insert(0, String::new());
+        lines.insert(0, format!("# {}", title));
+    }
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
+
+impl<'a> Page<'a> {
+    fn text(&mut self, raw: &str) {
+        if raw.is_empty() {
+            return;
+        }
+        let text = decode(raw);
+        if let Some(pre) = &mut self.pre {
+            pre.push_str(&text);
+            return;
+        }
+
+        for (i, word) in text.split(|c: char| c.is_whitespace()).enumerate() {
+            // whitespace around words becomes a single space
+            if i > 0 && !self.text.is_empty() && !self.text.ends_with(' ') {
+                self.text.push(' ');
+                if let Some((_, label)) = &mut self.link {
+                    if !label.is_empty() && !label.ends_with(' ') {
+                        label.push(' ');
+                    }
+                }
+            }
+            self.text.push_str(word);
+            if let Some((_, label)) = &mut self.link {
+                label.push_str(word);
+            }
+        }
+    }
+
+    fn tag(&mut self, name: &str, closing: bool, tag: &str) {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.flush();
+                if !closing {
+                    let level = name[1..].parse().unwrap_or(3);
+                    self.block = Block::Heading(std::cmp::min(level, 3));
+                }
+            }
+            "li" => {
+                self.flush();
+                if !closing {
+                    self.block = Block::Item;
+                }
+            }
+            "blockquote" => {
+                self.flush();
+                self.quote = if closing {
+                    self.quote.saturating_sub(1)
+                } else {
+                    self.quote + 1
+                };
+            }
+            "pre" => {
+                if closing {
+                    if let Some(pre) = self.pre.take() {
+                        self.preformatted(&pre);
+                    }
+                } else if self.pre.is_none() {
+                    self.flush();
+                    self.pre = Some(String::new());
+                }
+            }
+            "br" => {
+                if let Some(pre) = &mut self.pre {
+                    pre.push('\n');
+                } else {
+                    let block = self.block;
+                    self.flush();
+                    self.block = block;
+                }
+            }
+            "hr" => self.flush(),
+            "td" | "th" => self.text(" "),
+            "a" if closing => {
+                if let Some((href, label)) = self.link.take() {
+                    let label = label.trim().to_string();
+                    self.links.push((href, label));
+                }
+            }
+            "a" => {
+                self.link = attribute(tag, "href").map(|href| (href, String::new()));
+            }
+            "img" if !closing => {
+                if let Some(src) = attribute(tag, "src") {
+                    let alt = attribute(tag, "alt").unwrap_or_default();
+                    self.links.push((src, collapse(&alt)));
+                }
+            }
+            _ if BLOCKS.contains(&name) => self.flush(),
+            _ => (),
+        }
+    }
+
+    // Ends the current block and lists the links it contained.
+    fn flush(&mut self) {
+        if let Some((href, label)) = self.link.take() {
+            self.links.push((href, label));
+        }
+        let url = self.url;
+        let text = self.text.trim().to_string();
+        let links: Vec<(String, String)> = self
+            .links
+            .drain(..)
+            .filter_map(|(href, label)| resolve(url, &href).map(|url| (url, label)))
+            .collect();
+        let block = self.block;
+        self.text.clear();
+        self.block = Block::Paragraph;
+
+        if text.is_empty() && links.is_empty() {
+            return;
+        }
+
+        // blocks are separated by an empty line, list items are not
+        let together = block == Block::Item && self.last == Some(Block::Item);
+        if !together && self.lines.last().is_some_and(|line| !line.is_empty()) {
+            self.lines.push(String::new());
+        }
+        self.last = Some(block);
+
+        // a block that is only a link, like a menu entry, is shown as the link
+        let only_link =
+            links.len() == 1 && links[0].1 == text && !matches!(block, Block::Heading(_));
+        if !text.is_empty() && !only_link {
+            let quote = if self.quote > 0 { "> " } else { "" };
+            let line = match block {
+                Block::Heading(level) => format!("{} {}", "#".repeat(level), text),
+                Block::Item => format!("{}* {}", quote, text),
+                Block::Paragraph if quote.is_empty() => plain(&text),
+                Block::Paragraph => format!("{}{}", quote, text),
+            };
+            self.lines.push(line);
+        }
+        for (url, label) in links {
+            if label.is_empty() {
+                self.lines.push(format!("=> {}", url));
+            } else {
+                self.lines.push(format!("=> {} {}", url, label));
+            }
+        }
+    }
+
+    fn preformatted(&mut self, pre: &str) {
+        let pre = pre.trim_matches('\n');
+        if pre.trim().is_empty() {
+            return;
+        }
+        if self.lines.last().is_some_and(|line| !line.is_empty()) {
+            self.lines.push(String::new());
+        }
+        self.lines.push(String::from("```"));
+        for line in pre.lines() {
+            // a line can't close the block early
+            if line.starts_with("```") {
+                self.lines.push(format!(" {}", line));
+            } else {
+                self.lines.push(line.trim_end().to_string());
+            }
+        }
+        self.lines.push(String::from("```"));
+        self.last = Some(Block::Paragraph);
+    }
+}
+
+// Reads the tag at the start of `html`: its lowercase name, whether it
+// closes an element, the whole tag and what follows it.
+fn tag(html: &str) -> Option<(String, bool, &str, &str)> {
+    let inner = &html[1..];
+    let (closing, inner) = match inner.strip_prefix('/') {
+        Some(inner) => (true, inner),
+        None => (false, inner),
+    };
+    if !inner.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let mut quote = None;
+    let mut end = None;
+    for (i, c) in html.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '>') => {
+                end = Some(i);
+                break;
+            }
+            _ => (),
+        }
+    }
+    let end = end.unwrap_or(html.len() - 1);
+
+    let name: String = inner
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+    Some((name, closing, &html[..=end], &html[end + 1..]))
+}
+
+// Where the closing tag of `name` starts, or the end of `html`.
+fn find_closing(html: &str, name: &str) -> usize {
+    let lowercase = html.to_ascii_lowercase();
+    lowercase.find(&format!("</{}", name)).unwrap_or(html.len())
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let tag = tag.trim_start_matches('<').trim_end_matches('>');
+    let mut rest = tag.trim_start_matches(|c: char| !c.is_whitespace());
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() {
+            return None;
+        }
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let key = &rest[..end];
+        rest = rest[end..].trim_start();
+
+        let mut value = "";
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (found, next) = match after.chars().next() {
+                Some(q) if q == '"' || q == '\'' => match after[1..].find(q) {
+                    Some(end) => (&after[1..end + 1], &after[end + 2..]),
+                    None => (&after[1..], ""),
+                },
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = found;
+            rest = next;
+        }
+
+        if key.eq_ignore_ascii_case(name) {
+            return Some(decode(value).trim().to_string());
+        }
+    }
+}
+
+// Makes a link absolute. On Gopher the item type is guessed from the name,
+// since an HTML page doesn't know it.
+fn resolve(url: &Url, href: &str) -> Option<String> {
+    if href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") {
+        return None;
+    }
+    if !url.scheme().starts_with("gopher") {
+        return url.join(href).ok().map(|url| url.to_string());
+    }
+
+    let mut selector = url.clone();
+    selector.set_path(
+        url.path()
+            .get(2..)
+            .filter(|p| p.starts_with('/'))
+            .unwrap_or("/"),
+    );
+    let mut link = selector.join(href).ok()?;
+    if link.scheme() == url.scheme() && link.host() == url.host() && link.port() == url.port() {
+        let path = link.path().to_lowercase();
+        let item_type = if path.ends_with('/') {
+            '1'
+        } else if path.ends_with(".html") || path.ends_with(".htm") {
+            'h'
+        } else if path.ends_with(".gif") {
+            'g'
+        } else if path.ends_with(".png") || path.ends_with(".jpg") || path.ends_with(".jpeg") {
+            'I'
+        } else if path.ends_with(".txt") || path.ends_with(".md") || path.ends_with(".gmi") {
+            '0'
+        } else {
+            '9'
+        };
+        let path = format!("/{}{}", item_type, link.path());
+        link.set_path(&path);
+    }
+    Some(link.to_string())
+}
+
+// Paragraphs starting like a gemtext line are shifted by a space.
+fn plain(text: &str) -> String {
+    if ["#", "*", ">", "=>", "```"]
+        .iter()
+        .any(|prefix| text.starts_with(prefix))
+    {
+        format!(" {}", text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn decode(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut decoded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| entity(&rest[1..end + 1]).map(|c| (c, end + 2)));
+        match entity {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number
+            .strip_prefix('x')
+            .or_else(|| number.strip_prefix('X'))
+        {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return std::char::from_u32(code);
+    }
+
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        "ndash" => Some('–'),
+        "mdash" => Some('—'),
+        "hellip" => Some('…'),
+        "lsquo" => Some('‘'),
+        "rsquo" => Some('’'),
+        "ldquo" => Some('“'),
+        "rdquo" => Some('”'),
+        "laquo" => Some('«'),
+        "raquo" => Some('»'),
+        "bull" => Some('•'),
+        "middot" => Some('·'),
+        "copy" => Some('©'),
+        "reg" => Some('®'),
+        "deg" => Some('°'),
+        "times" => Some('×'),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_gemtext() {
+    let url = Url::parse("gemini://typed-hole.org/blog/index.html").unwrap();
+    let html = r#"<!DOCTYPE html>
+<html>
+<head><title>Typed hole</title><style>p { color: red; }</style></head>
+<body>
+<!-- navigation -->
+<ul>
+  <li><a href="/">Home</a></li>
+  <li><a href="about.html">About</a></li>
+</ul>
+<h1>Castor &amp; friends</h1>
+<p>A <b>browser</b> for
+   <a href="gemini://gemini.circumlunar.space/">Gemini</a> and Gopher.</p>
+<script>document.write("<p>hidden</p>");</script>
+<pre>
+fn main() {
+    println!("&lt;hello&gt;");
+}
+</pre>
+<blockquote><p>Small is beautiful</p></blockquote>
+<ol><li>One</li><li>Two<br>lines</li></ol>
+<p># not a heading</p>
+<p><img src="cat.png" alt="A cat"></p>
+</body>
+</html>"#;
+
+    let expected = "=> gemini://typed-hole.org/ Home
+=> gemini://typed-hole.org/blog/about.html About
+
+# Castor & friends
+
+A browser for Gemini and Gopher.
+=> gemini://gemini.circumlunar.space/ Gemini
+
+```
+fn main() {
+    println!(\"<hello>\");
+}
+```
+
+> Small is beautiful
+
+* One
+* Two
+* lines
+
+ # not a heading
+
+=> gemini://typed-hole.org/blog/cat.png A cat
+";
+    assert_eq!(gemtext(&url, html), expected);
+}
+
+#[test]
+fn test_title() {
+    let url = Url::parse("gemini://typed-hole.org/").unwrap();
+    assert_eq!(
+        gemtext(&url, "<title>Typed\n hole</title><p>Hello</p>"),
+        "# Typed hole\n\nHello\n"
+    );
+    assert_eq!(
+        gemtext(&url, "a < b &unknown; &#233;"),
+        "a < b &unknown; é\n"
+    );
+}
+
+#[test]
+fn test_gopher_links() {
+    let url = Url::parse("gopher://typed-hole.org/h/blog/index.html").unwrap();
+    let html = r#"<p><a href="post.html">Post</a> <a href="/notes.txt">Notes</a>
+<a href="../">Up</a> <a href="https://typed-hole.org/">Web</a></p>"#;
+    assert_eq!(
+        gemtext(&url, html),
+        "Post Notes Up Web
+=> gopher://typed-hole.org/h/blog/post.html Post
+=> gopher://typed-hole.org/0/notes.txt Notes
+=> gopher://typed-hole.org/1/ Up
+=> https://typed-hole.org/ Web
+"
+    );
+}
@@ -0,0 +1,85 @@
+extern crate dirs;
+
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+
+// Server certificates are trusted on first use: the fingerprint seen on the
+// first visit is remembered and any other certificate is refused afterwards.
+pub fn check(host: &str, certificate: &[u8]) -> Result<(), String> {
+    let fingerprint = fingerprint(certificate);
+
+    match find(&content()?, host) {
+        Some(known) if known == fingerprint => Ok(()),
+        Some(_) => Err(format!(
+            "The certificate of {} changed since your last visit.\nSomeone may be impersonating it, Castor will not connect.",
+            host
+        )),
+        None => add(host, &fingerprint),
+    }
+}
+
+pub fn fingerprint(certificate: &[u8]) -> String {
+    openssl::sha::sha256(certificate)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub fn content() -> Result<String, String> {
+    let mut file = known_hosts_file()?;
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .map_err(|e| format!("Could not read the known hosts\n{}", e))?;
+
+    Ok(content)
+}
+
+fn find<'a>(content: &'a str, host: &str) -> Option<&'a str> {
+    content.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (Some(known_host), Some(fingerprint)) if known_host == host => Some(fingerprint),
+            _ => None,
+        }
+    })
+}
+
+fn add(host: &str, fingerprint: &str) -> Result<(), String> {
+    let mut file = known_hosts_file()?;
+    let entry = format!("{} {}\n", host, fingerprint);
+    file.write_all(entry.as_bytes())
+        .map_err(|e| format!("Could not save the known hosts\n{}", e))
+}
+
+fn known_hosts_file() -> Result<File, String> {
+    let mut known_hosts =
+        dirs::data_local_dir().ok_or_else(|| String::from("Could not find the data directory"))?;
+    known_hosts.push("castor");
+    fs::create_dir_all(&known_hosts).map_err(|e| e.to_string())?;
+    known_hosts.push("known_hosts");
+    let file_path = known_hosts.into_os_string();
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .read(true)
+        .open(file_path)
+        .map_err(|e| format!("Could not open the known hosts\n{}", e))
+}
+
+#[test]
+fn test_find() {
+    let content = "typed-hole.org:70 abcd\ngopher.example.org:7070 ef01\n";
+    assert_eq!(find(content, "gopher.example.org:7070"), Some("ef01"));
+    assert_eq!(find(content, "gopher.example.org:70"), None);
+}
+
+#[test]
+fn test_fingerprint() {
+    assert_eq!(
+        fingerprint(b""),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+}
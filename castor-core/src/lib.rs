@@ -0,0 +1,26 @@
+// Everything Castor needs to fetch and understand pages, without GTK:
+// protocol clients, parsers, links, statuses, URL resolution, client
+// certificates, feeds and settings.
+#[macro_use]
+extern crate lazy_static;
+
+pub mod absolute_url;
+pub mod charset;
+pub mod client;
+pub mod feeds;
+pub mod file;
+pub mod finger;
+pub mod gemini;
+pub mod gopher;
+pub mod html;
+pub mod known_hosts;
+pub mod markdown;
+pub mod nex;
+pub mod protocols;
+pub mod settings;
+pub mod spartan;
+pub mod status;
+pub mod titan;
+
+pub use absolute_url::AbsoluteUrl;
+pub use protocols::{About, File, Finger, Gemini, Gopher, Nex, Protocol, Scheme, Spartan, Titan};
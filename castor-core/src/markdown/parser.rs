@@ -0,0 +1,411 @@
+// Markdown, read line by line like gemtext: headings, lists, quotes, code
+// blocks and paragraphs, with emphasis, code and links inside them.
+#[derive(Debug, PartialEq)]
+pub enum TextElement {
+    H1(Vec<Span>),
+    H2(Vec<Span>),
+    // and smaller headings
+    H3(Vec<Span>),
+    ListItem(Vec<Span>),
+    Quote(Vec<Span>),
+    Text(Vec<Span>),
+    // a line of a code block, as is
+    Code(String),
+    Rule,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Span {
+    Text(String),
+    Emphasis(String),
+    Strong(String),
+    Code(String),
+    // label and target, relative targets are left as they are
+    Link(String, String),
+}
+
+// `code` tells whether the previous lines left a code block open. Lines of
+// text are joined into paragraphs until a blank line or another block.
+pub fn parse(content: &str, code: &mut bool) -> Vec<TextElement> {
+    let mut parsed = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        let fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+        if *code && !fence {
+            parsed.push(TextElement::Code(line.to_string()));
+            continue;
+        }
+
+        let element = if fence { None } else { block(line) };
+        match element {
+            None if !fence && !trimmed.is_empty() => paragraph.push(trimmed.trim_end()),
+            // indented lines can't start a code block inside a paragraph
+            Some(TextElement::Code(_)) if !paragraph.is_empty() && !trimmed.is_empty() => {
+                paragraph.push(trimmed.trim_end())
+            }
+            element => {
+                if !paragraph.is_empty() {
+                    parsed.push(TextElement::Text(inline(&paragraph.join(" "))));
+                    paragraph.clear();
+                }
+                if fence {
+                    *code = !*code;
+                } else {
+                    parsed.push(element.unwrap_or(TextElement::Text(vec![])));
+                }
+            }
+        }
+    }
+    if !paragraph.is_empty() {
+        parsed.push(TextElement::Text(inline(&paragraph.join(" "))));
+    }
+    parsed
+}
+
+// None for a line of text, which belongs to a paragraph.
+fn block(line: &str) -> Option<TextElement> {
+    let trimmed = line.trim_start();
+
+    let element = if let Some(level) = heading_level(trimmed) {
+        let text = trimmed[level..].trim().trim_end_matches('#').trim_end();
+        let spans = inline(text);
+        match level {
+            1 => TextElement::H1(spans),
+            2 => TextElement::H2(spans),
+            _ => TextElement::H3(spans),
+        }
+    } else if is_rule(trimmed) {
+        TextElement::Rule
+    } else if let Some(quote) = trimmed.strip_prefix('>') {
+        TextElement::Quote(inline(quote.trim()))
+    } else if let Some(item) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| trimmed.strip_prefix(marker))
+    {
+        TextElement::ListItem(inline(item.trim()))
+    } else if is_ordered_item(trimmed) {
+        // the number is kept, the list character alone would lose the order
+        TextElement::ListItem(inline(trimmed.trim_end()))
+    } else if line.starts_with("    ") || line.starts_with('\t') {
+        TextElement::Code(line.trim_start_matches('\t').replacen("    ", "", 1))
+    } else if let Some((label, target)) = reference(trimmed) {
+        TextElement::Text(vec![Span::Link(label.to_string(), target.to_string())])
+    } else {
+        return None;
+    };
+    Some(element)
+}
+
+// "## Title" is a level 2 heading, "#hashtag" isn't a heading.
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    if (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' ')) {
+        Some(level)
+    } else {
+        None
+    }
+}
+
+// "1. First" or "1) First"
+fn is_ordered_item(line: &str) -> bool {
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    let rest = &line[digits..];
+    (1..=9).contains(&digits) && (rest.starts_with(". ") || rest.starts_with(") "))
+}
+
+// ---, *** or ___, spaces allowed in between.
+fn is_rule(line: &str) -> bool {
+    let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|marker| chars.iter().all(|c| c == marker))
+}
+
+// [label]: URL
+fn reference(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix('[')?;
+    let end = rest.find("]:")?;
+    let target = rest[end + 2..].split_whitespace().next()?;
+    Some((&rest[..end], target))
+}
+
+pub fn inline(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let found = match c {
+            '\\' => rest[1..].chars().next().map(|escaped| {
+                let len = 1 + escaped.len_utf8();
+                (Span::Text(escaped.to_string()), len)
+            }),
+            '`' => delimited(rest, "`").map(|(code, len)| (Span::Code(code.to_string()), len)),
+            '*' | '_' if rest[1..].starts_with(c) => {
+                let marker = &rest[..2];
+                delimited(rest, marker).map(|(text, len)| (Span::Strong(text.to_string()), len))
+            }
+            // snake_case words are not emphasis
+            '_' if plain.ends_with(|c: char| c.is_alphanumeric()) => None,
+            '*' | '_' => delimited(rest, &rest[..1])
+                .filter(|(text, _)| !text.starts_with(' '))
+                .map(|(text, len)| (Span::Emphasis(text.to_string()), len)),
+            '!' if rest[1..].starts_with('[') => {
+                link(&rest[1..]).map(|(span, len)| (span, len + 1))
+            }
+            '[' => link(rest),
+            '<' => autolink(rest),
+            _ => None,
+        };
+
+        match found {
+            Some((span, len)) => {
+                match span {
+                    Span::Text(text) => plain.push_str(&text),
+                    span => {
+                        if !plain.is_empty() {
+                            spans.push(Span::Text(std::mem::take(&mut plain)));
+                        }
+                        spans.push(span);
+                    }
+                }
+                rest = &rest[len..];
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span::Text(plain));
+    }
+    spans
+}
+
+// Text between an opening and a closing marker, and the length of it all.
+fn delimited<'a>(text: &'a str, marker: &str) -> Option<(&'a str, usize)> {
+    let inner = &text[marker.len()..];
+    let end = inner.find(marker)?;
+    if end == 0 {
+        return None;
+    }
+    Some((&inner[..end], marker.len() * 2 + end))
+}
+
+// [label](target "title")
+fn link(text: &str) -> Option<(Span, usize)> {
+    let label_end = text.find("](")?;
+    let label = &text[1..label_end];
+    let rest = &text[label_end + 2..];
+    let target_end = rest.find(')')?;
+    let target = rest[..target_end].split_whitespace().next()?;
+
+    let label = inline(label)
+        .into_iter()
+        .map(|span| match span {
+            Span::Text(text) | Span::Emphasis(text) | Span::Strong(text) | Span::Code(text) => text,
+            Span::Link(label, _) => label,
+        })
+        .collect::<String>();
+    let label = if label.is_empty() {
+        target.to_string()
+    } else {
+        label
+    };
+    Some((
+        Span::Link(label, target.to_string()),
+        label_end + 2 + target_end + 1,
+    ))
+}
+
+// <https://example.org>
+fn autolink(text: &str) -> Option<(Span, usize)> {
+    let end = text.find('>')?;
+    let target = &text[1..end];
+    if target.contains("://") || target.starts_with("mailto:") {
+        Some((Span::Link(target.to_string(), target.to_string()), end + 1))
+    } else {
+        None
+    }
+}
+
+// Breaks spans into lines of at most `width` characters, at spaces. Words
+// longer than a line are left whole.
+pub fn wrap(spans: &[Span], width: usize) -> Vec<Vec<Span>> {
+    let mut lines = vec![vec![]];
+    let mut column = 0;
+
+    for span in spans {
+        let (text, rebuild): (&str, Box<dyn Fn(String) -> Span>) = match span {
+            Span::Text(text) => (text, Box::new(Span::Text)),
+            Span::Emphasis(text) => (text, Box::new(Span::Emphasis)),
+            Span::Strong(text) => (text, Box::new(Span::Strong)),
+            Span::Code(text) => (text, Box::new(Span::Code)),
+            Span::Link(label, target) => {
+                let target = target.clone();
+                (
+                    label,
+                    Box::new(move |label| Span::Link(label, target.clone())),
+                )
+            }
+        };
+
+        let mut piece = String::new();
+        for word in text.split_inclusive(' ') {
+            let length = word.trim_end().chars().count();
+            if column > 0 && column + length > width {
+                let before = std::mem::take(&mut piece);
+                if !before.trim_end().is_empty() {
+                    lines
+                        .last_mut()
+                        .unwrap()
+                        .push(rebuild(before.trim_end().to_string()));
+                }
+                if let Some(Span::Text(text)) = lines.last_mut().unwrap().last_mut() {
+                    text.truncate(text.trim_end().len());
+                }
+                lines.push(vec![]);
+                column = 0;
+            }
+            piece.push_str(word);
+            column += word.chars().count();
+        }
+        if !piece.is_empty() {
+            lines.last_mut().unwrap().push(rebuild(piece));
+        }
+    }
+    lines
+}
+
+#[test]
+fn test_parse() {
+    let content = "# Castor *0.8*
+Some **bold** and `code`, see [the docs](docs.md).
+## Install
+- run `make`
+1. Ordered
+> Quoted
+```
+# not a heading
+```
+---
+#hashtag
+[home]: gemini://typed-hole.org/
+";
+    let mut code = false;
+    let parsed = parse(content, &mut code);
+
+    assert_eq!(
+        parsed,
+        vec![
+            TextElement::H1(vec![
+                Span::Text(String::from("Castor ")),
+                Span::Emphasis(String::from("0.8"))
+            ]),
+            TextElement::Text(vec![
+                Span::Text(String::from("Some ")),
+                Span::Strong(String::from("bold")),
+                Span::Text(String::from(" and ")),
+                Span::Code(String::from("code")),
+                Span::Text(String::from(", see ")),
+                Span::Link(String::from("the docs"), String::from("docs.md")),
+                Span::Text(String::from(".")),
+            ]),
+            TextElement::H2(vec![Span::Text(String::from("Install"))]),
+            TextElement::ListItem(vec![
+                Span::Text(String::from("run ")),
+                Span::Code(String::from("make"))
+            ]),
+            TextElement::ListItem(vec![Span::Text(String::from("1. Ordered"))]),
+            TextElement::Quote(vec![Span::Text(String::from("Quoted"))]),
+            TextElement::Code(String::from("# not a heading")),
+            TextElement::Rule,
+            TextElement::Text(vec![Span::Text(String::from("#hashtag"))]),
+            TextElement::Text(vec![Span::Link(
+                String::from("home"),
+                String::from("gemini://typed-hole.org/")
+            )]),
+        ]
+    );
+    assert!(!code);
+}
+
+#[test]
+fn test_parse_paragraphs() {
+    let content = "A paragraph
+on *two*   
+    lines.
+
+Another one
+2) ordered
+10. item
+";
+    let mut code = false;
+    let parsed = parse(content, &mut code);
+
+    assert_eq!(
+        parsed,
+        vec![
+            TextElement::Text(vec![
+                Span::Text(String::from("A paragraph on ")),
+                Span::Emphasis(String::from("two")),
+                Span::Text(String::from(" lines.")),
+            ]),
+            TextElement::Text(vec![]),
+            TextElement::Text(vec![Span::Text(String::from("Another one"))]),
+            TextElement::ListItem(vec![Span::Text(String::from("2) ordered"))]),
+            TextElement::ListItem(vec![Span::Text(String::from("10. item"))]),
+        ]
+    );
+}
+
+#[test]
+fn test_inline() {
+    assert_eq!(
+        inline("snake_case_name and 2 * 3 * 4 \\*not\\*"),
+        vec![Span::Text(String::from(
+            "snake_case_name and 2 * 3 * 4 *not*"
+        ))]
+    );
+    assert_eq!(
+        inline("![logo](logo.png) <gemini://example.org/>"),
+        vec![
+            Span::Link(String::from("logo"), String::from("logo.png")),
+            Span::Text(String::from(" ")),
+            Span::Link(
+                String::from("gemini://example.org/"),
+                String::from("gemini://example.org/")
+            ),
+        ]
+    );
+    assert_eq!(
+        inline("[unclosed](link"),
+        vec![Span::Text(String::from("[unclosed](link"))]
+    );
+}
+
+#[test]
+fn test_wrap() {
+    let spans = inline("a few words and [a link label](x) here");
+    let lines = wrap(&spans, 12);
+
+    assert_eq!(
+        lines,
+        vec![
+            vec![Span::Text(String::from("a few words"))],
+            vec![
+                Span::Text(String::from("and ")),
+                Span::Link(String::from("a link"), String::from("x"))
+            ],
+            vec![
+                Span::Link(String::from("label"), String::from("x")),
+                Span::Text(String::from(" here"))
+            ],
+        ]
+    );
+}
@@ -0,0 +1,99 @@
+use percent_encoding::percent_decode;
+use std::io::Write;
+use std::net::{SocketAddr::V4, SocketAddr::V6, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::client::Stream;
+use crate::Protocol;
+
+pub fn get_stream<T: Protocol>(url: T) -> Result<(Option<Vec<u8>>, Stream), String> {
+    let url = url.get_source_url();
+    let host = url.host_str().unwrap().to_string();
+    let port = url.port().unwrap_or(1900);
+    let urlf = format!("{}:{}", host, port);
+
+    match urlf.to_socket_addrs() {
+        Ok(mut addrs_iter) => match addrs_iter.next() {
+            Some(socket_addr) => {
+                let socket_addr = match socket_addr {
+                    V4(ip) => V4(ip),
+                    V6(ip) => match addrs_iter.next() {
+                        Some(addr) => addr,
+                        None => V6(ip),
+                    },
+                };
+
+                match TcpStream::connect_timeout(&socket_addr, Duration::new(5, 0)) {
+                    Ok(mut stream) => {
+                        stream
+                            .set_read_timeout(Some(crate::settings::read_timeout()))
+                            .map_err(|e| e.to_string())?;
+                        // the path is sent as it is on the server, not percent-encoded
+                        let mut request: Vec<u8> = percent_decode(url.path().as_bytes()).collect();
+                        request.extend_from_slice(b"\r\n");
+                        stream
+                            .write_all(&request)
+                            .map_err(|e| format!("Could not send request\n{}", e))?;
+
+                        Ok((None, Box::new(stream)))
+                    }
+                    Err(e) => Err(format!("Could not connect to {}\n{}", urlf, e)),
+                }
+            }
+            None => Err(format!("Could not connect to {}\n", urlf)),
+        },
+        Err(e) => Err(format!("Could not connect to {}\n{}", urlf, e)),
+    }
+}
+
+// Directories are listed with gemtext link lines, everything else is plain text.
+pub fn is_directory(url: &url::Url) -> bool {
+    url.path().is_empty() || url.path().ends_with('/')
+}
+
+#[cfg(test)]
+fn serve_once(response: &'static [u8]) -> (u16, std::thread::JoinHandle<Vec<u8>>) {
+    use std::io::{BufRead, BufReader};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let handle = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request = vec![];
+        reader.read_until(b'\n', &mut request).unwrap();
+
+        reader.get_mut().write_all(response).unwrap();
+        request
+    });
+
+    (port, handle)
+}
+
+#[test]
+fn test_get_stream() {
+    use std::io::Read;
+
+    let (port, server) = serve_once(b"Hello from Nex\n");
+    let url = crate::protocols::Nex {
+        source: format!("nex://127.0.0.1:{}/my%20file.txt", port),
+    };
+
+    let (meta, mut body) = get_stream(url).unwrap();
+    let mut content = String::new();
+    body.read_to_string(&mut content).unwrap();
+
+    assert_eq!(meta, None);
+    assert_eq!(content, "Hello from Nex\n");
+    assert_eq!(server.join().unwrap(), b"/my file.txt\r\n".to_vec());
+}
+
+#[test]
+fn test_is_directory() {
+    let url = |source: &str| url::Url::parse(source).unwrap();
+    assert!(is_directory(&url("nex://nightfall.city")));
+    assert!(is_directory(&url("nex://nightfall.city/")));
+    assert!(is_directory(&url("nex://nightfall.city/nex/")));
+    assert!(!is_directory(&url("nex://nightfall.city/nex/info.txt")));
+}
@@ -0,0 +1,156 @@
+use url::Url;
+
+pub trait Protocol {
+    fn get_source_str(&self) -> &str;
+    fn get_source_url(&self) -> Url;
+    fn get_scheme(&self) -> Scheme;
+}
+
+pub struct About {
+    pub source: String,
+}
+pub struct Gemini {
+    pub source: String,
+}
+pub struct Gopher {
+    pub source: String,
+}
+pub struct Nex {
+    pub source: String,
+}
+pub struct Spartan {
+    pub source: String,
+}
+pub struct Titan {
+    pub source: String,
+}
+pub struct Finger {
+    pub source: String,
+}
+pub struct File {
+    pub source: String,
+}
+
+impl Protocol for About {
+    fn get_source_str(&self) -> &str {
+        &self.source
+    }
+
+    fn get_source_url(&self) -> Url {
+        Url::parse(&self.source).unwrap()
+    }
+
+    fn get_scheme(&self) -> Scheme {
+        Scheme::About
+    }
+}
+
+impl Protocol for Finger {
+    fn get_source_str(&self) -> &str {
+        &self.source
+    }
+
+    fn get_source_url(&self) -> Url {
+        Url::parse(&self.source).unwrap()
+    }
+
+    fn get_scheme(&self) -> Scheme {
+        Scheme::Finger
+    }
+}
+
+impl Protocol for File {
+    fn get_source_str(&self) -> &str {
+        &self.source
+    }
+
+    fn get_source_url(&self) -> Url {
+        Url::parse(&self.source).unwrap()
+    }
+
+    fn get_scheme(&self) -> Scheme {
+        Scheme::File
+    }
+}
+
+impl Protocol for Gemini {
+    fn get_source_str(&self) -> &str {
+        &self.source
+    }
+
+    fn get_source_url(&self) -> Url {
+        Url::parse(&self.source).unwrap()
+    }
+
+    fn get_scheme(&self) -> Scheme {
+        Scheme::Gemini
+    }
+}
+
+impl Protocol for Gopher {
+    fn get_source_str(&self) -> &str {
+        &self.source
+    }
+
+    fn get_source_url(&self) -> Url {
+        Url::parse(&self.source).unwrap()
+    }
+
+    fn get_scheme(&self) -> Scheme {
+        Scheme::Gopher
+    }
+}
+
+impl Protocol for Nex {
+    fn get_source_str(&self) -> &str {
+        &self.source
+    }
+
+    fn get_source_url(&self) -> Url {
+        Url::parse(&self.source).unwrap()
+    }
+
+    fn get_scheme(&self) -> Scheme {
+        Scheme::Nex
+    }
+}
+
+impl Protocol for Spartan {
+    fn get_source_str(&self) -> &str {
+        &self.source
+    }
+
+    fn get_source_url(&self) -> Url {
+        Url::parse(&self.source).unwrap()
+    }
+
+    fn get_scheme(&self) -> Scheme {
+        Scheme::Spartan
+    }
+}
+
+impl Protocol for Titan {
+    fn get_source_str(&self) -> &str {
+        &self.source
+    }
+
+    fn get_source_url(&self) -> Url {
+        Url::parse(&self.source).unwrap()
+    }
+
+    fn get_scheme(&self) -> Scheme {
+        Scheme::Titan
+    }
+}
+
+#[derive(PartialEq)]
+pub enum Scheme {
+    About,
+    File,
+    Finger,
+    Gemini,
+    Gopher,
+    Nex,
+    Spartan,
+    Titan,
+}
@@ -0,0 +1,724 @@
+extern crate dirs;
+
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde_derive::Deserialize;
+
+#[derive(Deserialize)]
+struct Settings {
+    general: Option<General>,
+    colors: Option<Color>,
+    characters: Option<Character>,
+    fonts: Option<Font>,
+    gopher: Option<Gopher>,
+    feeds: Option<Feeds>,
+    charsets: Option<HashMap<String, String>>,
+}
+
+#[derive(Deserialize)]
+struct General {
+    start_url: Option<String>,
+    max_width: Option<usize>,
+    max_body_size: Option<usize>,
+    download_dir: Option<String>,
+    inline_images: Option<bool>,
+    html_reader: Option<bool>,
+    read_timeout: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct Gopher {
+    try_tls: Option<Vec<String>>,
+    grid_layout: Option<bool>,
+    grid_layout_hosts: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct Feeds {
+    refresh_interval: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct Color {
+    h1: Option<String>,
+    h2: Option<String>,
+    h3: Option<String>,
+    list: Option<String>,
+    text: Option<String>,
+    background: Option<String>,
+    quote: Option<QuoteColor>,
+    ansi: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct Character {
+    h1: Option<String>,
+    h2: Option<String>,
+    h3: Option<String>,
+    list: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Font {
+    finger: Option<FontAttr>,
+    gemini: Option<GeminiFontAttr>,
+    gopher: Option<FontAttr>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FontAttr {
+    family: Option<String>,
+    style: Option<String>,
+    size: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiFontAttr {
+    text: Option<FontAttr>,
+    h1: Option<FontAttr>,
+    h2: Option<FontAttr>,
+    h3: Option<FontAttr>,
+    list: Option<FontAttr>,
+    quote: Option<FontAttr>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuoteColor {
+    foreground: Option<String>,
+    background: Option<String>,
+}
+
+pub fn start_url() -> Option<String> {
+    match read().general {
+        Some(general) => general.start_url,
+        None => None,
+    }
+}
+
+pub fn max_width() -> Option<usize> {
+    match read().general {
+        Some(general) => general.max_width,
+        None => None,
+    }
+}
+
+const DEFAULT_MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+pub fn max_body_size() -> usize {
+    match read().general {
+        Some(general) => general.max_body_size.unwrap_or(DEFAULT_MAX_BODY_SIZE),
+        None => DEFAULT_MAX_BODY_SIZE,
+    }
+}
+
+pub fn download_dir() -> Option<String> {
+    read().general?.download_dir
+}
+
+// Where downloads go, `download_dir` or the usual downloads directory.
+pub fn download_directory() -> PathBuf {
+    match download_dir() {
+        Some(dir) => match (dir.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(dir),
+        },
+        None => dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_else(std::env::temp_dir),
+    }
+}
+
+const DEFAULT_READ_TIMEOUT: u64 = 30;
+
+// How long a server can stay silent, in seconds. It applies to every read,
+// so slow or endless responses go on as long as they keep sending.
+pub fn read_timeout() -> Duration {
+    let seconds = read()
+        .general
+        .and_then(|general| general.read_timeout)
+        .unwrap_or(DEFAULT_READ_TIMEOUT);
+    Duration::from_secs(seconds)
+}
+
+pub fn inline_images() -> bool {
+    match read().general {
+        Some(general) => general.inline_images.unwrap_or(true),
+        None => true,
+    }
+}
+
+// HTML pages are converted to gemtext instead of shown as their source,
+// when turned on.
+pub fn html_reader() -> bool {
+    match read().general {
+        Some(general) => general.html_reader.unwrap_or(false),
+        None => false,
+    }
+}
+
+// Hosts to try TLS with first when following gopher:// links.
+pub fn gopher_try_tls(host: &str) -> bool {
+    match read().gopher.and_then(|gopher| gopher.try_tls) {
+        Some(hosts) => hosts.iter().any(|h| h == host),
+        None => false,
+    }
+}
+
+// Menus laid out as monospace text, everywhere or on some hosts.
+pub fn gopher_grid_layout(host: &str) -> bool {
+    match read().gopher {
+        Some(gopher) => {
+            let hosts = gopher.grid_layout_hosts.unwrap_or_default();
+            gopher.grid_layout.unwrap_or(false) || hosts.iter().any(|h| h == host)
+        }
+        None => false,
+    }
+}
+
+const DEFAULT_FEEDS_REFRESH_INTERVAL: u64 = 60;
+
+// How often subscribed feeds are fetched again, in minutes.
+pub fn feeds_refresh_interval() -> Duration {
+    let minutes = read()
+        .feeds
+        .and_then(|feeds| feeds.refresh_interval)
+        .unwrap_or(DEFAULT_FEEDS_REFRESH_INTERVAL);
+    Duration::from_secs(minutes.max(1) * 60)
+}
+
+// Charset of the gopher and finger pages of a host, guessed when not set.
+pub fn charset(host: &str) -> Option<String> {
+    read().charsets?.remove(host)
+}
+
+const DEFAULT_FONT: &str = "serif";
+const DEFAULT_FONT_STYLE: &str = "normal";
+// Font sizes are in points.
+const DEFAULT_FONT_SIZE: i32 = 11;
+const DEFAULT_H1_FONT_SIZE: i32 = 16;
+const DEFAULT_H2_FONT_SIZE: i32 = 13;
+const DEFAULT_H3_FONT_SIZE: i32 = 12;
+
+fn finger_font_family() -> Option<String> {
+    read().fonts?.finger?.family
+}
+
+fn finger_font_size() -> Option<i32> {
+    read().fonts?.finger?.size.or(Some(DEFAULT_FONT_SIZE))
+}
+
+pub fn get_finger_font_family() -> String {
+    match finger_font_family() {
+        Some(family) => family,
+        None => String::from(DEFAULT_FONT),
+    }
+}
+
+pub fn get_finger_font_size() -> i32 {
+    finger_font_size().unwrap_or(DEFAULT_FONT_SIZE)
+}
+
+fn gemini_text_font_family() -> Option<String> {
+    read().fonts?.gemini?.text?.family
+}
+
+pub fn get_gemini_text_font_family() -> String {
+    match gemini_text_font_family() {
+        Some(family) => family,
+        None => String::from(DEFAULT_FONT),
+    }
+}
+
+fn gemini_text_font_size() -> Option<i32> {
+    read().fonts?.gemini?.text?.size
+}
+
+pub fn get_gemini_text_font_size() -> i32 {
+    gemini_text_font_size().unwrap_or(DEFAULT_FONT_SIZE)
+}
+
+fn gemini_h1_font_family() -> Option<String> {
+    read().fonts?.gemini?.h1?.family
+}
+
+fn gemini_h1_font_size() -> Option<i32> {
+    read().fonts?.gemini?.h1?.size
+}
+
+fn gemini_h1_font_style() -> Option<String> {
+    read().fonts?.gemini?.h1?.style
+}
+
+pub fn get_gemini_h1_font_size() -> i32 {
+    gemini_h1_font_size().unwrap_or(DEFAULT_H1_FONT_SIZE)
+}
+
+pub fn get_gemini_h1_font_family() -> String {
+    match gemini_h1_font_family() {
+        Some(family) => family,
+        None => String::from(DEFAULT_FONT),
+    }
+}
+
+pub fn get_gemini_h1_font_style() -> String {
+    match gemini_h1_font_style() {
+        Some(style) => style,
+        None => String::from(DEFAULT_FONT_STYLE),
+    }
+}
+
+fn gemini_h2_font_family() -> Option<String> {
+    read().fonts?.gemini?.h2?.family
+}
+
+fn gemini_h2_font_size() -> Option<i32> {
+    read().fonts?.gemini?.h2?.size
+}
+
+fn gemini_h2_font_style() -> Option<String> {
+    read().fonts?.gemini?.h2?.style
+}
+
+pub fn get_gemini_h2_font_size() -> i32 {
+    gemini_h2_font_size().unwrap_or(DEFAULT_H2_FONT_SIZE)
+}
+
+pub fn get_gemini_h2_font_family() -> String {
+    match gemini_h2_font_family() {
+        Some(family) => family,
+        None => String::from(DEFAULT_FONT),
+    }
+}
+
+pub fn get_gemini_h2_font_style() -> String {
+    match gemini_h2_font_style() {
+        Some(style) => style,
+        None => String::from(DEFAULT_FONT_STYLE),
+    }
+}
+
+fn gemini_h3_font_family() -> Option<String> {
+    read().fonts?.gemini?.h3?.family
+}
+
+fn gemini_h3_font_size() -> Option<i32> {
+    read().fonts?.gemini?.h3?.size
+}
+
+fn gemini_h3_font_style() -> Option<String> {
+    read().fonts?.gemini?.h3?.style
+}
+
+pub fn get_gemini_h3_font_size() -> i32 {
+    gemini_h3_font_size().unwrap_or(DEFAULT_H3_FONT_SIZE)
+}
+
+pub fn get_gemini_h3_font_family() -> String {
+    match gemini_h3_font_family() {
+        Some(family) => family,
+        None => String::from(DEFAULT_FONT),
+    }
+}
+
+pub fn get_gemini_h3_font_style() -> String {
+    match gemini_h3_font_style() {
+        Some(style) => style,
+        None => String::from(DEFAULT_FONT_STYLE),
+    }
+}
+
+fn gemini_list_font_family() -> Option<String> {
+    read().fonts?.gemini?.list?.family
+}
+
+fn gemini_list_font_size() -> Option<i32> {
+    read().fonts?.gemini?.list?.size
+}
+
+fn gemini_list_font_style() -> Option<String> {
+    read().fonts?.gemini?.list?.style
+}
+
+fn gemini_quote_font_family() -> Option<String> {
+    read().fonts?.gemini?.quote?.family
+}
+
+fn gemini_quote_font_size() -> Option<i32> {
+    read().fonts?.gemini?.quote?.size
+}
+
+fn gemini_quote_font_style() -> Option<String> {
+    read().fonts?.gemini?.quote?.style
+}
+
+pub fn get_gemini_list_font_size() -> i32 {
+    gemini_list_font_size().unwrap_or(DEFAULT_FONT_SIZE)
+}
+
+pub fn get_gemini_list_font_family() -> String {
+    match gemini_list_font_family() {
+        Some(family) => family,
+        None => String::from(DEFAULT_FONT),
+    }
+}
+
+pub fn get_gemini_list_font_style() -> String {
+    match gemini_list_font_style() {
+        Some(style) => style,
+        None => String::from(DEFAULT_FONT_STYLE),
+    }
+}
+
+pub fn get_gemini_quote_font_size() -> i32 {
+    gemini_quote_font_size().unwrap_or(DEFAULT_FONT_SIZE)
+}
+
+pub fn get_gemini_quote_font_family() -> String {
+    match gemini_quote_font_family() {
+        Some(family) => family,
+        None => String::from(DEFAULT_FONT),
+    }
+}
+
+pub fn get_gemini_quote_font_style() -> String {
+    match gemini_quote_font_style() {
+        Some(style) => style,
+        None => String::from("italic"),
+    }
+}
+
+fn gopher_font_family() -> Option<String> {
+    read().fonts?.gopher?.family
+}
+
+fn gopher_font_size() -> Option<i32> {
+    read().fonts?.gopher?.size
+}
+
+pub fn get_gopher_font_family() -> String {
+    match gopher_font_family() {
+        Some(family) => family,
+        None => String::from(DEFAULT_FONT),
+    }
+}
+
+pub fn get_gopher_font_size() -> i32 {
+    gopher_font_size().unwrap_or(DEFAULT_FONT_SIZE)
+}
+
+fn h1_color() -> Option<String> {
+    read().colors?.h1
+}
+
+pub fn get_h1_color() -> String {
+    match h1_color() {
+        Some(color) => color,
+        None => String::from("#9932CC"),
+    }
+}
+
+fn h2_color() -> Option<String> {
+    read().colors?.h2
+}
+
+pub fn get_h2_color() -> String {
+    match h2_color() {
+        Some(color) => color,
+        None => String::from("#FF1493"),
+    }
+}
+
+fn h3_color() -> Option<String> {
+    read().colors?.h3
+}
+
+pub fn get_h3_color() -> String {
+    match h3_color() {
+        Some(color) => color,
+        None => String::from("#87CEFA"),
+    }
+}
+
+fn list_color() -> Option<String> {
+    read().colors?.list
+}
+
+pub fn get_list_color() -> String {
+    match list_color() {
+        Some(color) => color,
+        None => String::from("green"),
+    }
+}
+
+fn quote_color() -> Option<QuoteColor> {
+    read().colors?.quote
+}
+
+pub fn get_gemini_quote_foreground_color() -> String {
+    match quote_color() {
+        Some(color) => match color.foreground {
+            Some(color) => color,
+            None => String::from("#e4e4e4"),
+        },
+        None => String::from("#e4e4e4"),
+    }
+}
+
+pub fn get_gemini_quote_background_color() -> String {
+    match quote_color() {
+        Some(color) => match color.background {
+            Some(color) => color,
+            None => String::from("grey"),
+        },
+        None => String::from("grey"),
+    }
+}
+
+fn text_color() -> Option<String> {
+    read().colors?.text
+}
+
+pub fn get_text_color() -> String {
+    match text_color() {
+        Some(color) => color,
+        None => String::from("black"),
+    }
+}
+
+// The 16 colours used by ANSI escape sequences.
+pub fn ansi_palette() -> Option<Vec<String>> {
+    read().colors?.ansi
+}
+
+pub fn background_color() -> Option<String> {
+    read().colors?.background
+}
+
+fn h1_character() -> Option<String> {
+    read().characters?.h1
+}
+
+pub fn get_h1_character() -> String {
+    match h1_character() {
+        Some(char) => char,
+        None => String::new(),
+    }
+}
+
+fn h2_character() -> Option<String> {
+    read().characters?.h2
+}
+
+pub fn get_h2_character() -> String {
+    match h2_character() {
+        Some(char) => char,
+        None => String::new(),
+    }
+}
+
+fn h3_character() -> Option<String> {
+    read().characters?.h3
+}
+
+pub fn get_h3_character() -> String {
+    match h3_character() {
+        Some(char) => char,
+        None => String::new(),
+    }
+}
+
+fn list_character() -> Option<String> {
+    read().characters?.list
+}
+
+pub fn get_list_character() -> String {
+    match list_character() {
+        Some(char) => char,
+        None => String::from("■"),
+    }
+}
+
+// Every setting with the value in use, and whether it was set in
+// settings.toml or is the default.
+pub fn effective() -> Vec<(String, String, bool)> {
+    let file: toml::Value = toml::from_str(&content()).unwrap();
+    let points = |size: i32| format!("{}pt", size);
+    let or_none = |value: Option<String>| value.unwrap_or_else(|| String::from("none"));
+    let list = |value: Option<Vec<String>>| match value {
+        Some(values) if !values.is_empty() => values.join(", "),
+        _ => String::from("none"),
+    };
+    let settings = read();
+    let gopher = settings.gopher;
+
+    let mut entries = vec![
+        ("general.start_url", or_none(start_url())),
+        (
+            "general.max_width",
+            or_none(max_width().map(|w| w.to_string())),
+        ),
+        ("general.max_body_size", max_body_size().to_string()),
+        (
+            "general.download_dir",
+            download_directory().display().to_string(),
+        ),
+        ("general.inline_images", inline_images().to_string()),
+        ("general.html_reader", html_reader().to_string()),
+        (
+            "general.read_timeout",
+            format!("{}s", read_timeout().as_secs()),
+        ),
+        ("colors.h1", get_h1_color()),
+        ("colors.h2", get_h2_color()),
+        ("colors.h3", get_h3_color()),
+        ("colors.list", get_list_color()),
+        ("colors.text", get_text_color()),
+        ("colors.background", or_none(background_color())),
+        (
+            "colors.quote.foreground",
+            get_gemini_quote_foreground_color(),
+        ),
+        (
+            "colors.quote.background",
+            get_gemini_quote_background_color(),
+        ),
+        ("colors.ansi", list(ansi_palette())),
+        ("characters.h1", get_h1_character()),
+        ("characters.h2", get_h2_character()),
+        ("characters.h3", get_h3_character()),
+        ("characters.list", get_list_character()),
+        ("fonts.finger.family", get_finger_font_family()),
+        ("fonts.finger.size", points(get_finger_font_size())),
+        ("fonts.gemini.text.family", get_gemini_text_font_family()),
+        (
+            "fonts.gemini.text.size",
+            points(get_gemini_text_font_size()),
+        ),
+        ("fonts.gemini.h1.family", get_gemini_h1_font_family()),
+        ("fonts.gemini.h1.size", points(get_gemini_h1_font_size())),
+        ("fonts.gemini.h1.style", get_gemini_h1_font_style()),
+        ("fonts.gemini.h2.family", get_gemini_h2_font_family()),
+        ("fonts.gemini.h2.size", points(get_gemini_h2_font_size())),
+        ("fonts.gemini.h2.style", get_gemini_h2_font_style()),
+        ("fonts.gemini.h3.family", get_gemini_h3_font_family()),
+        ("fonts.gemini.h3.size", points(get_gemini_h3_font_size())),
+        ("fonts.gemini.h3.style", get_gemini_h3_font_style()),
+        ("fonts.gemini.list.family", get_gemini_list_font_family()),
+        (
+            "fonts.gemini.list.size",
+            points(get_gemini_list_font_size()),
+        ),
+        ("fonts.gemini.list.style", get_gemini_list_font_style()),
+        ("fonts.gemini.quote.family", get_gemini_quote_font_family()),
+        (
+            "fonts.gemini.quote.size",
+            points(get_gemini_quote_font_size()),
+        ),
+        ("fonts.gemini.quote.style", get_gemini_quote_font_style()),
+        ("fonts.gopher.family", get_gopher_font_family()),
+        ("fonts.gopher.size", points(get_gopher_font_size())),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect::<Vec<_>>();
+
+    let (try_tls, grid_layout, grid_layout_hosts) = match gopher {
+        Some(gopher) => (gopher.try_tls, gopher.grid_layout, gopher.grid_layout_hosts),
+        None => (None, None, None),
+    };
+    entries.push((String::from("gopher.try_tls"), list(try_tls)));
+    entries.push((
+        String::from("gopher.grid_layout"),
+        grid_layout.unwrap_or(false).to_string(),
+    ));
+    entries.push((
+        String::from("gopher.grid_layout_hosts"),
+        list(grid_layout_hosts),
+    ));
+    entries.push((
+        String::from("feeds.refresh_interval"),
+        format!("{}min", feeds_refresh_interval().as_secs() / 60),
+    ));
+
+    let mut charsets: Vec<(String, String)> =
+        settings.charsets.unwrap_or_default().into_iter().collect();
+    charsets.sort();
+    for (host, charset) in charsets {
+        entries.push((format!("charsets.\"{}\"", host), charset));
+    }
+
+    entries
+        .into_iter()
+        .map(|(key, value)| {
+            let from_file = lookup(&file, &key).is_some();
+            (key, value, from_file)
+        })
+        .collect()
+}
+
+// Finds a value by its dotted key, quoted parts can contain dots.
+fn lookup<'a>(value: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    let mut value = value;
+    let mut rest = key;
+    while !rest.is_empty() {
+        let (part, next) = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"')?;
+            (&quoted[..end], &quoted[end + 1..])
+        } else {
+            match rest.find('.') {
+                Some(end) => (&rest[..end], &rest[end..]),
+                None => (rest, ""),
+            }
+        };
+        value = value.get(part)?;
+        rest = next.trim_start_matches('.');
+    }
+    Some(value)
+}
+
+pub fn path() -> PathBuf {
+    let mut settings = dirs::config_dir().unwrap();
+    settings.push("castor");
+    fs::create_dir_all(&settings).unwrap();
+    settings.push("settings.toml");
+    settings
+}
+
+fn content() -> String {
+    let mut file = settings_file();
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .expect("Unable to read file");
+    content
+}
+
+fn read() -> Settings {
+    let settings: Settings = toml::from_str(&content()).unwrap();
+    settings
+}
+
+fn settings_file() -> File {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .read(true)
+        .open(path())
+        .expect("file not found")
+}
+
+#[test]
+fn test_lookup() {
+    let file: toml::Value =
+        toml::from_str("[fonts.gemini.h1]\nsize = 18\n\n[charsets]\n\"sdf.org\" = \"cp437\"\n")
+            .unwrap();
+    assert!(lookup(&file, "fonts.gemini.h1.size").is_some());
+    assert!(lookup(&file, "fonts.gemini.h2.size").is_none());
+    assert!(lookup(&file, "charsets.\"sdf.org\"").is_some());
+    assert!(lookup(&file, "charsets.sdf").is_none());
+}
@@ -0,0 +1,144 @@
+use percent_encoding::percent_decode;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr::V4, SocketAddr::V6, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::client::Stream;
+use crate::Protocol;
+
+// <STATUS><SPACE><META><CR><LF> where META is at most 1024 bytes
+const MAX_HEADER_SIZE: u64 = 1028;
+
+// The query string of the URL is uploaded as the request data,
+// that's how "=:" prompts send what was typed.
+pub fn get_stream<T: Protocol>(url: T) -> Result<(Option<Vec<u8>>, Stream), String> {
+    let url = url.get_source_url();
+    let host = url.host_str().unwrap_or("").to_string();
+    let port = url.port().unwrap_or(300);
+    let urlf = format!("{}:{}", host, port);
+
+    let path = if url.path().is_empty() {
+        "/"
+    } else {
+        url.path()
+    };
+    let data: Vec<u8> = match url.query() {
+        Some(query) => percent_decode(query.as_bytes()).collect(),
+        None => vec![],
+    };
+
+    match urlf.to_socket_addrs() {
+        Ok(mut addrs_iter) => match addrs_iter.next() {
+            Some(socket_addr) => {
+                let socket_addr = match socket_addr {
+                    V4(ip) => V4(ip),
+                    V6(ip) => match addrs_iter.next() {
+                        Some(addr) => addr,
+                        None => V6(ip),
+                    },
+                };
+
+                match TcpStream::connect_timeout(&socket_addr, Duration::new(5, 0)) {
+                    Ok(mut stream) => {
+                        stream
+                            .set_read_timeout(Some(crate::settings::read_timeout()))
+                            .map_err(|e| e.to_string())?;
+                        let request = format!("{} {} {}\r\n", host, path, data.len());
+                        stream
+                            .write_all(request.as_bytes())
+                            .and_then(|_| stream.write_all(&data))
+                            .map_err(|e| format!("Could not send request\n{}", e))?;
+
+                        let mut reader = BufReader::new(stream);
+                        let mut meta = vec![];
+                        reader
+                            .by_ref()
+                            .take(MAX_HEADER_SIZE)
+                            .read_until(b'\n', &mut meta)
+                            .map_err(|e| format!("Failed to read response\n{}", e))?;
+
+                        if !meta.ends_with(b"\r\n") {
+                            return Err("Failed to read response (missing crlf)".to_string());
+                        }
+
+                        Ok((Some(meta), Box::new(reader)))
+                    }
+                    Err(e) => Err(format!("Could not connect to {}\n{}", urlf, e)),
+                }
+            }
+            None => Err(format!("Could not connect to {}\n", urlf)),
+        },
+        Err(e) => Err(format!("Could not connect to {}\n{}", urlf, e)),
+    }
+}
+
+#[cfg(test)]
+fn serve_once(response: &'static [u8]) -> (u16, std::thread::JoinHandle<Vec<u8>>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let handle = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request = vec![];
+        reader.read_until(b'\n', &mut request).unwrap();
+
+        let length: usize = String::from_utf8_lossy(&request)
+            .trim_end()
+            .rsplit(' ')
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        let mut data = vec![0; length];
+        reader.read_exact(&mut data).unwrap();
+        request.extend(data);
+
+        reader.get_mut().write_all(response).unwrap();
+        request
+    });
+
+    (port, handle)
+}
+
+#[test]
+fn test_get_stream() {
+    let (port, server) = serve_once(b"2 text/gemini\r\n# Spartan\n");
+    let url = crate::protocols::Spartan {
+        source: format!("spartan://127.0.0.1:{}/", port),
+    };
+
+    let (meta, mut body) = get_stream(url).unwrap();
+    let mut content = String::new();
+    body.read_to_string(&mut content).unwrap();
+
+    assert_eq!(meta, Some(b"2 text/gemini\r\n".to_vec()));
+    assert_eq!(content, "# Spartan\n");
+    assert_eq!(server.join().unwrap(), b"127.0.0.1 / 0\r\n".to_vec());
+}
+
+#[test]
+fn test_get_stream_uploads_query() {
+    let (port, server) = serve_once(b"3 /guestbook\r\n");
+    let url = crate::protocols::Spartan {
+        source: format!("spartan://127.0.0.1:{}/sign?hello%20world", port),
+    };
+
+    let (meta, _body) = get_stream(url).unwrap();
+
+    assert_eq!(meta, Some(b"3 /guestbook\r\n".to_vec()));
+    assert_eq!(
+        server.join().unwrap(),
+        b"127.0.0.1 /sign 11\r\nhello world".to_vec()
+    );
+}
+
+#[test]
+fn test_get_stream_missing_crlf() {
+    let (port, _server) = serve_once(b"2 text/gemini\n");
+    let url = crate::protocols::Spartan {
+        source: format!("spartan://127.0.0.1:{}/", port),
+    };
+
+    assert!(get_stream(url).is_err());
+}
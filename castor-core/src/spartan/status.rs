@@ -0,0 +1,64 @@
+extern crate regex;
+use regex::Regex;
+
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    Success(String),
+    Redirect(String),
+    ClientError(String),
+    ServerError(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseError;
+
+const STATUS_REGEX: &str = r"^([2345]) (.*)\r\n$";
+
+impl FromStr for Status {
+    type Err = ParseError;
+
+    // Parses a &str into an instance of 'Status'
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let status_regexp = Regex::new(STATUS_REGEX).unwrap();
+
+        match status_regexp.captures(line) {
+            Some(caps) => {
+                let code = caps.get(1).map_or("", |m| m.as_str());
+                let meta = caps.get(2).map_or("", |m| m.as_str()).to_string();
+
+                match code {
+                    "2" => Ok(Status::Success(meta)),
+                    "3" => Ok(Status::Redirect(meta)),
+                    "4" => Ok(Status::ClientError(meta)),
+                    "5" => Ok(Status::ServerError(meta)),
+                    _ => Err(ParseError),
+                }
+            }
+            None => Err(ParseError),
+        }
+    }
+}
+
+#[test]
+fn test_parse_success() {
+    assert_eq!(
+        Status::from_str("2 text/gemini; charset=utf-8\r\n"),
+        Ok(Status::Success(String::from("text/gemini; charset=utf-8")))
+    );
+}
+
+#[test]
+fn test_parse_redirect() {
+    assert_eq!(
+        Status::from_str("3 /new/place\r\n"),
+        Ok(Status::Redirect(String::from("/new/place")))
+    );
+}
+
+#[test]
+fn test_parse_invalid() {
+    assert_eq!(Status::from_str("20 text/gemini\r\n"), Err(ParseError));
+    assert_eq!(Status::from_str("2 text/gemini\n"), Err(ParseError));
+}
@@ -46,10 +46,11 @@ impl FromStr for Status {
                 let code_str = caps.get(1).map_or("", |m| m.as_str());
                 let meta_str = caps.get(2).map_or("", |m| m.as_str());
 
-                let code: i16 = code_str.parse().expect("2");
-                let meta = meta_str.to_string();
-
-                Ok(make_status(code, meta))
+                // \d also matches digits of other scripts
+                match code_str.parse() {
+                    Ok(code) => Ok(make_status(code, meta_str.to_string())),
+                    Err(_) => Err(ParseError),
+                }
             }
             None => Err(ParseError),
         }
@@ -82,3 +83,14 @@ fn make_status(code: i16, meta: String) -> Status {
         _ => Status::Unknown(meta),
     }
 }
+
+#[test]
+fn test_from_str() {
+    assert!(matches!(
+        Status::from_str("20 text/gemini\r\n"),
+        Ok(Status::Success(meta)) if meta == "text/gemini"
+    ));
+    assert!(matches!(Status::from_str("99 ?\r\n"), Ok(Status::Unknown(_))));
+    assert!(Status::from_str("20 text/gemini\n").is_err());
+    assert!(Status::from_str("٢٠ text/gemini\r\n").is_err());
+}
@@ -0,0 +1,97 @@
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use std::io::Write;
+use url::{Position, Url};
+
+use crate::client::Stream;
+use crate::Protocol;
+
+// Characters that would end a parameter or the path if left as they are.
+const PARAMETER: &AsciiSet = &CONTROLS.add(b' ').add(b';').add(b'=').add(b'?').add(b'#');
+
+// Sends the data to a titan:// URL. The response is a regular Gemini one,
+// usually a redirect to the page that was just written.
+pub fn upload<T: Protocol>(
+    url: T,
+    mime: &str,
+    token: &str,
+    data: &[u8],
+) -> Result<(Option<Vec<u8>>, Stream), String> {
+    let url = url.get_source_url();
+    // connect sets the read timeout, the upload itself can stall too
+    let mut stream = crate::gemini::client::connect(&url)?;
+    stream
+        .get_ref()
+        .set_write_timeout(Some(crate::settings::read_timeout()))
+        .map_err(|e| e.to_string())?;
+
+    let request = format!("{}\r\n", request_url(&url, mime, data.len(), token));
+    stream
+        .write_all(request.as_bytes())
+        .and_then(|_| stream.write_all(data))
+        .map_err(|e| format!("Could not send request\n{}", e))?;
+
+    crate::gemini::client::read_response(stream)
+}
+
+// titan://host/path;mime=<type>;size=<bytes>;token=<token>?query
+pub fn request_url(url: &Url, mime: &str, size: usize, token: &str) -> String {
+    let mut request = format!(
+        "{};mime={};size={}",
+        &url[..Position::AfterPath],
+        utf8_percent_encode(mime, PARAMETER),
+        size
+    );
+    if !token.is_empty() {
+        request.push_str(&format!(";token={}", utf8_percent_encode(token, PARAMETER)));
+    }
+    request.push_str(&url[Position::AfterPath..]);
+    request
+}
+
+// The titan:// URL to upload the source of a gemini:// page to.
+pub fn edit_url(url: &Url) -> Option<Url> {
+    switch_scheme(url, "gemini", "titan")
+}
+
+// The gemini:// page a titan:// URL writes to.
+pub fn page_url(url: &Url) -> Option<Url> {
+    switch_scheme(url, "titan", "gemini")
+}
+
+fn switch_scheme(url: &Url, from: &str, to: &str) -> Option<Url> {
+    if url.scheme() != from {
+        return None;
+    }
+    Url::parse(&format!("{}{}", to, &url[Position::AfterScheme..])).ok()
+}
+
+#[test]
+fn test_request_url() {
+    let url = Url::parse("titan://typed-hole.org/gemlog/post.gmi").unwrap();
+    assert_eq!(
+        request_url(&url, "text/gemini", 42, ""),
+        "titan://typed-hole.org/gemlog/post.gmi;mime=text/gemini;size=42"
+    );
+}
+
+#[test]
+fn test_request_url_with_token_and_query() {
+    let url = Url::parse("titan://typed-hole.org/upload?draft").unwrap();
+    assert_eq!(
+        request_url(&url, "text/plain; charset=utf-8", 3, "s3cr=t;"),
+        "titan://typed-hole.org/upload;mime=text/plain%3B%20charset%3Dutf-8;size=3;token=s3cr%3Dt%3B?draft"
+    );
+}
+
+#[test]
+fn test_edit_url() {
+    let url = Url::parse("gemini://typed-hole.org:1965/gemlog/").unwrap();
+    assert_eq!(
+        edit_url(&url),
+        Some(Url::parse("titan://typed-hole.org:1965/gemlog/").unwrap())
+    );
+    assert_eq!(
+        edit_url(&Url::parse("gopher://typed-hole.org/").unwrap()),
+        None
+    );
+}
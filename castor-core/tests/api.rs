@@ -0,0 +1,67 @@
+// The library as another program sees it, without any of the GUI.
+use std::str::FromStr;
+use url::Url;
+
+use castor_core::gemini::link::Link;
+use castor_core::gemini::parser::{self, TextElement};
+use castor_core::status::Status;
+use castor_core::{AbsoluteUrl, Gemini, Gopher, Protocol, Scheme};
+
+#[test]
+fn test_gemtext_links() {
+    let content = String::from("# Capsule\n=> /gemlog/ Gemlog\n=> gopher://typed-hole.org/ Phlog");
+    let current = Url::parse("gemini://typed-hole.org/index.gmi").unwrap();
+
+    let links: Vec<Url> = parser::parse(content)
+        .into_iter()
+        .filter_map(|el| match el {
+            Ok(TextElement::LinkItem(line)) => Some(line),
+            _ => None,
+        })
+        .map(|line| match Link::from_str(&line) {
+            Ok(Link::Relative(url, _)) => Gemini { source: url }
+                .to_absolute_url(Some(&current))
+                .unwrap(),
+            Ok(Link::Gopher(url, _)) => url,
+            link => panic!("unexpected link {:?}", link),
+        })
+        .collect();
+
+    assert_eq!(
+        links,
+        vec![
+            Url::parse("gemini://typed-hole.org/gemlog/").unwrap(),
+            Url::parse("gopher://typed-hole.org/").unwrap(),
+        ]
+    );
+}
+
+#[test]
+fn test_status() {
+    match Status::from_str("31 gemini://typed-hole.org/new\r\n") {
+        Ok(Status::RedirectPermanent(url)) => assert_eq!(url, "gemini://typed-hole.org/new"),
+        status => panic!("unexpected status {:?}", status),
+    }
+    assert!(Status::from_str("hello\r\n").is_err());
+}
+
+#[test]
+fn test_gopher_address() {
+    let url = Gopher {
+        source: String::from("gopher://typed-hole.org/0/notes%20on%20castor.txt"),
+    };
+    assert!(url.get_scheme() == Scheme::Gopher);
+
+    let address = castor_core::gopher::address::parse(&url.get_source_url());
+    assert_eq!(address.item_type, '0');
+    assert_eq!(address.request(), "/notes on castor.txt\r\n");
+}
+
+#[test]
+fn test_charset() {
+    use castor_core::charset::{self, Charset};
+
+    let data = b"caf\xe9";
+    assert!(charset::detect(data) == Some(Charset::Latin1));
+    assert_eq!(charset::decode(data, Charset::Latin1), "café");
+}
@@ -0,0 +1,188 @@
+// In-process servers answering scripted responses on localhost, for the
+// protocol tests. Every test binary gets its own home directory, with
+// settings that make timeouts short.
+#![allow(dead_code)]
+
+use openssl::asn1::Asn1Time;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+use openssl::ssl::{SslAcceptor, SslMethod, SslVerifyMode};
+use openssl::x509::{X509NameBuilder, X509};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::Once;
+use std::thread;
+use std::time::Duration;
+
+// Seconds, matches `read_timeout` in the test settings.
+pub const READ_TIMEOUT: u64 = 1;
+
+pub enum Reply {
+    Send(Vec<u8>),
+    // keeps the connection open without saying anything
+    Hang,
+    // sends the pieces half a read timeout apart
+    Slow(Vec<Vec<u8>>),
+}
+
+pub fn text(reply: &str) -> Reply {
+    Reply::Send(reply.as_bytes().to_vec())
+}
+
+static SETUP: Once = Once::new();
+
+pub fn home() -> PathBuf {
+    std::env::temp_dir().join(format!("castor-tests-{}", std::process::id()))
+}
+
+// Points the home directory somewhere empty, so the settings, known hosts
+// and certificates of the user running the tests are left alone.
+pub fn setup() {
+    SETUP.call_once(|| {
+        let home = home();
+        let config = home.join(".config").join("castor");
+        fs::create_dir_all(&config).unwrap();
+        fs::write(
+            config.join("settings.toml"),
+            format!("[general]\nread_timeout = {}\n", READ_TIMEOUT),
+        )
+        .unwrap();
+
+        std::env::set_var("HOME", &home);
+        for var in &["XDG_CONFIG_HOME", "XDG_DATA_HOME", "XDG_CACHE_HOME"] {
+            std::env::remove_var(var);
+        }
+    });
+}
+
+// Plain TCP server, `reply` gets the request line without its CRLF.
+pub fn tcp_server(reply: fn(&str) -> Reply) -> u16 {
+    setup();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream);
+                let request = read_request(&mut reader);
+                answer(reader.get_mut(), reply(&request));
+            });
+        }
+    });
+    port
+}
+
+// Gemini server with a self-signed certificate. It asks for a client
+// certificate, `reply` also gets the common name of the one sent.
+pub fn tls_server(reply: fn(&str, Option<String>) -> Reply) -> u16 {
+    setup();
+    let (cert, key) = self_signed("localhost");
+    let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    acceptor.set_private_key(&key).unwrap();
+    acceptor.set_certificate(&cert).unwrap();
+    acceptor.set_verify_callback(SslVerifyMode::PEER, |_, _| true);
+    let acceptor = acceptor.build();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let acceptor = acceptor.clone();
+            thread::spawn(move || {
+                let stream = match acceptor.accept(stream) {
+                    Ok(stream) => stream,
+                    Err(_) => return,
+                };
+                let client = stream
+                    .ssl()
+                    .peer_certificate()
+                    .and_then(|cert| common_name(&cert));
+
+                let mut reader = BufReader::new(stream);
+                let request = read_request(&mut reader);
+                answer(reader.get_mut(), reply(&request, client));
+            });
+        }
+    });
+    port
+}
+
+fn read_request<R: Read>(reader: &mut BufReader<R>) -> String {
+    let mut request = String::new();
+    reader.read_line(&mut request).ok();
+    request.trim_end_matches("\r\n").to_string()
+}
+
+fn answer<W: Write>(stream: &mut W, reply: Reply) {
+    match reply {
+        Reply::Send(data) => {
+            stream.write_all(&data).ok();
+            stream.flush().ok();
+        }
+        Reply::Hang => thread::sleep(Duration::from_secs(READ_TIMEOUT * 5)),
+        Reply::Slow(pieces) => {
+            for piece in pieces {
+                if stream
+                    .write_all(&piece)
+                    .and_then(|_| stream.flush())
+                    .is_err()
+                {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(READ_TIMEOUT * 500));
+            }
+        }
+    }
+}
+
+pub fn self_signed(name: &str) -> (X509, PKey<Private>) {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+
+    let mut subject = X509NameBuilder::new().unwrap();
+    subject.append_entry_by_text("CN", name).unwrap();
+    let subject = subject.build();
+
+    let mut cert = X509::builder().unwrap();
+    cert.set_version(2).unwrap();
+    cert.set_subject_name(&subject).unwrap();
+    cert.set_issuer_name(&subject).unwrap();
+    cert.set_pubkey(&key).unwrap();
+    cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    cert.set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+    cert.sign(&key, MessageDigest::sha256()).unwrap();
+    (cert.build(), key)
+}
+
+fn common_name(cert: &X509) -> Option<String> {
+    let entry = cert
+        .subject_name()
+        .entries_by_nid(openssl::nid::Nid::COMMONNAME)
+        .next()?;
+    Some(String::from_utf8_lossy(entry.data().as_slice()).to_string())
+}
+
+// Stores a client certificate for `host` where Castor looks for it.
+pub fn add_client_certificate(host: &str, name: &str) {
+    setup();
+    let (cert, key) = self_signed(name);
+    let rsa = key.rsa().unwrap();
+    fs::write(home().join(format!("{}.crt", host)), cert.to_pem().unwrap()).unwrap();
+    fs::write(
+        home().join(format!("{}.key", host)),
+        rsa.private_key_to_pem().unwrap(),
+    )
+    .unwrap();
+}
+
+pub fn read_all(mut stream: castor_core::client::Stream) -> Vec<u8> {
+    let mut data = vec![];
+    stream.read_to_end(&mut data).unwrap();
+    data
+}
@@ -0,0 +1,81 @@
+// Subscriptions to a gemlog served over Gemini and an RSS feed over Gopher.
+mod common;
+
+use url::Url;
+
+use castor_core::feeds::subscriptions;
+use common::{text, Reply};
+
+const GEMLOG: &str = "20 text/gemini\r\n# Gemlog
+=> 2020-05-17.gmi 2020-05-17 - Castor 0.8
+=> 2019-12-01.gmi 2019-12-01 - Older post
+=> /about.gmi About
+";
+
+const RSS: &str = "<rss version=\"2.0\"><channel><title>Phlog</title>
+<item><title>Phlog post</title><link>/0/post.txt</link>
+<pubDate>Mon, 02 Mar 2020 10:00:00 GMT</pubDate></item>
+</channel></rss>";
+
+fn gemini(request: &str, _client: Option<String>) -> Reply {
+    match Url::parse(request).unwrap().path() {
+        "/gemlog/" => text(GEMLOG),
+        _ => text("20 text/gemini\r\n# Not a feed\n"),
+    }
+}
+
+fn gopher(request: &str) -> Reply {
+    match request {
+        "/rss.xml" => text(RSS),
+        _ => Reply::Hang,
+    }
+}
+
+#[test]
+fn test_subscriptions() {
+    let gemlog = Url::parse(&format!(
+        "gemini://127.0.0.1:{}/gemlog/",
+        common::tls_server(gemini)
+    ))
+    .unwrap();
+    let phlog = Url::parse(&format!(
+        "gopher://127.0.0.1:{}/0/rss.xml",
+        common::tcp_server(gopher)
+    ))
+    .unwrap();
+
+    let feed = subscriptions::fetch(&gemlog).unwrap();
+    assert_eq!(feed.title, "Gemlog");
+    subscriptions::subscribe(&gemlog, &feed).unwrap();
+    let feed = subscriptions::fetch(&phlog).unwrap();
+    assert_eq!(feed.entries.len(), 1);
+    subscriptions::subscribe(&phlog, &feed).unwrap();
+    assert!(subscriptions::fetch(&gemlog.join("/").unwrap()).is_err());
+    assert_eq!(subscriptions::list(), vec![gemlog.clone(), phlog.clone()]);
+
+    subscriptions::refresh();
+    let titles: Vec<(String, String, bool)> = subscriptions::entries()
+        .into_iter()
+        .map(|(feed, entry, read)| (feed, entry.title, read))
+        .collect();
+    assert_eq!(
+        titles,
+        vec![
+            (String::from("Gemlog"), String::from("Castor 0.8"), false),
+            (String::from("Phlog"), String::from("Phlog post"), false),
+            (String::from("Gemlog"), String::from("Older post"), false),
+        ]
+    );
+
+    subscriptions::mark_read(&gemlog.join("2020-05-17.gmi").unwrap());
+    subscriptions::mark_read(&gemlog.join("/about.gmi").unwrap());
+    let read: Vec<bool> = subscriptions::entries()
+        .into_iter()
+        .map(|(_, _, read)| read)
+        .collect();
+    assert_eq!(read, vec![true, false, false]);
+
+    subscriptions::unsubscribe(&gemlog).unwrap();
+    assert_eq!(subscriptions::list(), vec![phlog]);
+    assert_eq!(subscriptions::entries().len(), 1);
+}
@@ -0,0 +1,65 @@
+// Finger queries against a local server.
+mod common;
+
+use std::time::{Duration, Instant};
+
+use castor_core::finger;
+use castor_core::Finger;
+use common::{text, Reply};
+
+fn get(url: &str) -> Result<String, String> {
+    let (_, body) = finger::client::get_stream(Finger {
+        source: url.to_string(),
+    })?;
+    Ok(String::from_utf8_lossy(&common::read_all(body)).to_string())
+}
+
+// Answers with the query line it received.
+fn reply(query: &str) -> Reply {
+    match query {
+        "hang" => Reply::Hang,
+        "" => text("Login  Name\r\nalice  Alice\r\n"),
+        query => text(&format!("query: [{}]\r\n", query)),
+    }
+}
+
+#[test]
+fn test_queries() {
+    let port = common::tcp_server(reply);
+    let queries = [
+        (
+            format!("finger://127.0.0.1:{}", port),
+            "Login  Name\r\nalice  Alice\r\n",
+        ),
+        (
+            format!("finger://alice@127.0.0.1:{}", port),
+            "query: [alice]\r\n",
+        ),
+        (
+            format!("finger://127.0.0.1:{}/w/alice", port),
+            "query: [/W alice]\r\n",
+        ),
+        (
+            format!("finger://alice@example.org@127.0.0.1:{}", port),
+            "query: [alice@example.org]\r\n",
+        ),
+    ];
+
+    for (url, response) in queries.iter() {
+        assert_eq!(get(url).unwrap(), *response, "{}", url);
+    }
+}
+
+#[test]
+fn test_timeout() {
+    let port = common::tcp_server(reply);
+    let start = Instant::now();
+    let (_, mut body) = finger::client::get_stream(Finger {
+        source: format!("finger://127.0.0.1:{}/hang", port),
+    })
+    .unwrap();
+
+    let mut data = vec![];
+    assert!(std::io::Read::read_to_end(&mut body, &mut data).is_err());
+    assert!(start.elapsed() < Duration::from_secs(common::READ_TIMEOUT * 4));
+}
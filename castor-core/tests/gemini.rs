@@ -0,0 +1,187 @@
+// Gemini requests against a local server with a self-signed certificate.
+mod common;
+
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use url::Url;
+
+use castor_core::client::{self, Chunk};
+use castor_core::gemini;
+use castor_core::status::Status;
+use castor_core::Gemini;
+use common::{text, Reply};
+
+const LARGE_SIZE: usize = 3 * 1024 * 1024;
+
+fn get(url: &str) -> Result<(String, Vec<u8>), String> {
+    let (meta, body) = gemini::client::get_stream(Gemini {
+        source: url.to_string(),
+    })?;
+    let meta = String::from_utf8_lossy(&meta.unwrap_or_default()).to_string();
+    Ok((meta, common::read_all(body)))
+}
+
+fn reply(request: &str, client: Option<String>) -> Reply {
+    let url = Url::parse(request).unwrap();
+    match url.path() {
+        "/" => text("20 text/gemini\r\n# Hello\n=> /next Next\n"),
+        "/lf" => text("20 text/gemini\n# No CR\n"),
+        "/old" => text("31 /new\r\n"),
+        "/new" => text("20 text/plain\r\nmoved here"),
+        "/loop" => text("30 /loop\r\n"),
+        "/local" => text("30 file:///etc/hostname\r\n"),
+        "/search" => match url.query() {
+            Some(query) => text(&format!("20 text/plain\r\n{}", query)),
+            None => text("10 Terms\r\n"),
+        },
+        "/private" => match client {
+            Some(name) => text(&format!("20 text/plain\r\n{}", name)),
+            None => text("60 Certificate required\r\n"),
+        },
+        "/large" => {
+            let mut data = b"20 application/octet-stream\r\n".to_vec();
+            data.resize(data.len() + LARGE_SIZE, b'x');
+            Reply::Send(data)
+        }
+        "/hang" => Reply::Hang,
+        "/slow" => Reply::Slow(
+            ["20 text/plain\r\n", "one\n", "two\n", "three\n", "four\n"]
+                .iter()
+                .map(|piece| piece.as_bytes().to_vec())
+                .collect(),
+        ),
+        _ => text("51 Not found\r\n"),
+    }
+}
+
+#[test]
+fn test_success() {
+    let port = common::tls_server(reply);
+    let (meta, body) = get(&format!("gemini://127.0.0.1:{}/", port)).unwrap();
+
+    assert_eq!(meta, "20 text/gemini\r\n");
+    assert_eq!(body, b"# Hello\n=> /next Next\n");
+    assert!(matches!(Status::from_str(&meta), Ok(Status::Success(mime)) if mime == "text/gemini"));
+
+    let (meta, _) = get(&format!("gemini://127.0.0.1:{}/nothing", port)).unwrap();
+    assert!(matches!(Status::from_str(&meta), Ok(Status::NotFound(_))));
+}
+
+#[test]
+fn test_missing_crlf() {
+    let port = common::tls_server(reply);
+    let error = get(&format!("gemini://127.0.0.1:{}/lf", port)).unwrap_err();
+    assert!(error.contains("missing crlf"), "{}", error);
+}
+
+#[test]
+fn test_redirect() {
+    let port = common::tls_server(reply);
+    let url = Url::parse(&format!("gemini://127.0.0.1:{}/old", port)).unwrap();
+    let (mime, body) = client::fetch(&url, 1024).unwrap();
+    assert_eq!(mime, "text/plain");
+    assert_eq!(body, b"moved here");
+
+    let url = Url::parse(&format!("gemini://127.0.0.1:{}/loop", port)).unwrap();
+    assert_eq!(client::fetch(&url, 1024).unwrap_err(), "Too many redirects");
+
+    let url = Url::parse(&format!("gemini://127.0.0.1:{}/local", port)).unwrap();
+    assert_eq!(
+        client::fetch(&url, 1024).unwrap_err(),
+        "Can't follow a redirect to a local file"
+    );
+}
+
+#[test]
+fn test_input() {
+    let port = common::tls_server(reply);
+    let (meta, _) = get(&format!("gemini://127.0.0.1:{}/search", port)).unwrap();
+    assert!(matches!(Status::from_str(&meta), Ok(Status::Input(prompt)) if prompt == "Terms"));
+
+    let (_, body) = get(&format!(
+        "gemini://127.0.0.1:{}/search?castor%20browser",
+        port
+    ))
+    .unwrap();
+    assert_eq!(body, b"castor%20browser");
+}
+
+#[test]
+fn test_client_certificate() {
+    let port = common::tls_server(reply);
+    common::add_client_certificate("localhost", "castor-test");
+
+    // the certificate is only sent to the host it's named after
+    let (meta, _) = get(&format!("gemini://127.0.0.1:{}/private", port)).unwrap();
+    assert!(matches!(
+        Status::from_str(&meta),
+        Ok(Status::ClientCertificateRequired(_))
+    ));
+
+    let (meta, body) = get(&format!("gemini://localhost:{}/private", port)).unwrap();
+    assert_eq!(meta, "20 text/plain\r\n");
+    assert_eq!(body, b"castor-test");
+}
+
+#[test]
+fn test_large_body() {
+    let port = common::tls_server(reply);
+    let source = format!("gemini://127.0.0.1:{}/large", port);
+
+    let (_, body) = get(&source).unwrap();
+    assert_eq!(body.len(), LARGE_SIZE);
+
+    let (_, stream) = gemini::client::get_stream(Gemini { source }).unwrap();
+    let mut received = 0;
+    let mut rest = None;
+    client::read_chunks(stream, 1024 * 1024, |chunk| {
+        match chunk {
+            Chunk::Data(data) => received += data.len(),
+            Chunk::TooLarge(stream) => rest = Some(common::read_all(stream)),
+            Chunk::Done => panic!("the body should be too large"),
+            Chunk::Error(e) => panic!("{}", e),
+        }
+        true
+    });
+
+    let rest = rest.unwrap();
+    assert!(received > 1024 * 1024 && received < LARGE_SIZE);
+    assert_eq!(received + rest.len(), LARGE_SIZE);
+}
+
+#[test]
+fn test_timeout() {
+    let port = common::tls_server(reply);
+    let start = Instant::now();
+    let error = get(&format!("gemini://127.0.0.1:{}/hang", port)).unwrap_err();
+
+    assert!(error.starts_with("Failed to read response"), "{}", error);
+    assert!(start.elapsed() < Duration::from_secs(common::READ_TIMEOUT * 4));
+}
+
+#[test]
+fn test_slow_stream() {
+    let port = common::tls_server(reply);
+    let (_, stream) = gemini::client::get_stream(Gemini {
+        source: format!("gemini://127.0.0.1:{}/slow", port),
+    })
+    .unwrap();
+    let start = Instant::now();
+    let mut received = vec![];
+    let mut done = false;
+
+    // the whole body takes longer than the read timeout, each piece doesn't
+    client::read_chunks(stream, 1024, |chunk| {
+        match chunk {
+            Chunk::Data(data) => received.extend(data),
+            Chunk::Done => done = true,
+            Chunk::TooLarge(_) => panic!("the body is small"),
+            Chunk::Error(e) => panic!("{}", e),
+        }
+        true
+    });
+
+    assert!(done);
+    assert_eq!(received, b"one\ntwo\nthree\nfour\n");
+    assert!(start.elapsed() > Duration::from_secs(common::READ_TIMEOUT));
+}
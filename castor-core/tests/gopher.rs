@@ -0,0 +1,156 @@
+// Gopher requests against a local server.
+mod common;
+
+use std::io::Read;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use castor_core::gopher;
+use castor_core::gopher::link::Link;
+use castor_core::gopher::parser::TextElement;
+use castor_core::Gopher;
+use common::{text, Reply};
+
+const MENU: &str = "iWelcome\t\terror.host\t1\r
+1Phlog\t/phlog\t127.0.0.1\t70\r
+0About\t/about.txt\t127.0.0.1\t70\r
+7Search\t/search\t127.0.0.1\t70\r
+.\r
+";
+
+// Lines a careless gophermap generator could write.
+const MALFORMED_MENU: &str = "1No host\t/nohost\r
+0No port\t/noport\texample.org\r
+1\t\t\t\r
+1Bad port\t/\texample.org\tseventy\r
+9\t/file.bin\texample.org\t70\r
+hNo URL\r
+8Telnet\r
+\t\t\r
++\t/mirror\r
+i\r
+1Still fine\t/fine\texample.org\t70\r
+";
+
+fn get(url: &str) -> Result<String, String> {
+    let (_, body) = gopher::client::get_stream(Gopher {
+        source: url.to_string(),
+    })?;
+    Ok(String::from_utf8_lossy(&common::read_all(body)).to_string())
+}
+
+fn reply(request: &str) -> Reply {
+    let mut parts = request.split('\t');
+    match (parts.next().unwrap_or(""), parts.next(), parts.next()) {
+        ("", None, None) => text(MENU),
+        ("/malformed", None, None) => text(MALFORMED_MENU),
+        ("/search", Some(terms), None) => {
+            text(&format!("iResults for {}\t\terror.host\t1\r\n", terms))
+        }
+        ("/about.txt", Some("+"), None) => text("+-1\r\nhello\r\n"),
+        ("/missing", Some("+"), None) => text("--1\r\n1 Item is not available\r\n"),
+        ("/hang", None, None) => Reply::Hang,
+        _ => text("3Not found\t\terror.host\t1\r\n"),
+    }
+}
+
+// The links of a menu, None for the lines that look like items but aren't.
+fn links(menu: String) -> Vec<Option<Link>> {
+    gopher::parser::parse(menu)
+        .into_iter()
+        .filter_map(|el| match el {
+            Ok(TextElement::LinkItem(line)) | Ok(TextElement::Search(line)) => Some(line),
+            _ => None,
+        })
+        .map(|line| Link::from_str(&line).ok())
+        .collect()
+}
+
+#[test]
+fn test_menu() {
+    let port = common::tcp_server(reply);
+    let menu = get(&format!("gopher://127.0.0.1:{}/1", port)).unwrap();
+    let links = links(menu);
+
+    assert_eq!(links.len(), 3);
+    match &links[0] {
+        Some(Link::Gopher(url, label)) => {
+            assert_eq!(url.as_str(), "gopher://127.0.0.1:70/1/phlog");
+            assert_eq!(label, "Phlog");
+        }
+        link => panic!("unexpected link {:?}", link),
+    }
+    assert!(matches!(&links[2], Some(Link::Search(_, label)) if label == "Search"));
+}
+
+#[test]
+fn test_malformed_menu() {
+    let port = common::tcp_server(reply);
+    let menu = get(&format!("gopher://127.0.0.1:{}/1/malformed", port)).unwrap();
+
+    let elements = gopher::parser::parse(menu.clone());
+    assert_eq!(elements.len(), MALFORMED_MENU.lines().count());
+
+    let links = links(menu);
+    match links.last() {
+        Some(Some(Link::Gopher(url, _))) => {
+            assert_eq!(url.as_str(), "gopher://example.org:70/1/fine")
+        }
+        link => panic!("unexpected link {:?}", link),
+    }
+}
+
+#[test]
+fn test_search() {
+    let port = common::tcp_server(reply);
+    let menu = get(&format!("gopher://127.0.0.1:{}/7/search%09castor", port)).unwrap();
+    assert!(menu.starts_with("iResults for castor\t"));
+}
+
+#[test]
+fn test_plus() {
+    let port = common::tcp_server(reply);
+    let body = get(&format!("gopher://127.0.0.1:{}/0/about.txt%09%09+", port)).unwrap();
+    assert_eq!(body, "hello\r\n");
+
+    let error = get(&format!("gopher://127.0.0.1:{}/0/missing%09%09+", port)).unwrap_err();
+    assert!(error.contains("Item is not available"), "{}", error);
+}
+
+#[test]
+fn test_gophers_pinned_certificate() {
+    let port = common::tls_server(|request, _| reply(request));
+    assert_eq!(
+        get(&format!("gophers://127.0.0.1:{}/", port)).unwrap(),
+        MENU
+    );
+    let host = format!("127.0.0.1:{} ", port);
+    assert!(castor_core::known_hosts::content()
+        .unwrap()
+        .lines()
+        .any(|line| line.starts_with(&host)));
+
+    // a certificate other than the one remembered is refused
+    let port = common::tls_server(|request, _| reply(request));
+    let known_hosts = common::home().join(".local/share/castor/known_hosts");
+    let mut content = std::fs::read_to_string(&known_hosts).unwrap();
+    content.push_str(&format!("127.0.0.1:{} 0000\n", port));
+    std::fs::write(&known_hosts, content).unwrap();
+
+    let error = get(&format!("gophers://127.0.0.1:{}/", port)).unwrap_err();
+    assert!(error.contains("changed since your last visit"), "{}", error);
+}
+
+#[test]
+fn test_timeout() {
+    let port = common::tcp_server(reply);
+    let start = Instant::now();
+    let (_, mut body) = gopher::client::get_stream(Gopher {
+        source: format!("gopher://127.0.0.1:{}/0/hang", port),
+    })
+    .unwrap();
+
+    let mut data = vec![];
+    assert!(body.read_to_end(&mut data).is_err());
+    assert!(start.elapsed() < Duration::from_secs(common::READ_TIMEOUT * 4));
+}
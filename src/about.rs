@@ -0,0 +1,268 @@
+// Pages generated by Castor itself, reachable as about:<name>.
+use url::Url;
+
+use crate::bookmarks;
+use crate::downloads;
+use crate::feeds::{self, subscriptions, Entry, Feed};
+use crate::gemini::certificate;
+use crate::settings;
+
+struct Page {
+    name: &'static str,
+    description: &'static str,
+    content: fn() -> String,
+}
+
+const PAGES: [Page; 7] = [
+    Page {
+        name: "about",
+        description: "This list",
+        content: index,
+    },
+    Page {
+        name: "bookmarks",
+        description: "Bookmarks",
+        content: bookmarks_page,
+    },
+    Page {
+        name: "certificates",
+        description: "Client certificates sent to Gemini hosts",
+        content: certificates,
+    },
+    Page {
+        name: "downloads",
+        description: "Downloads of this session",
+        content: downloads_page,
+    },
+    Page {
+        name: "feeds",
+        description: "Entries of the feeds you subscribed to",
+        content: feeds_page,
+    },
+    Page {
+        name: "help",
+        description: "Controls",
+        content: help,
+    },
+    Page {
+        name: "settings",
+        description: "Settings in use",
+        content: settings_page,
+    },
+];
+
+// Gemtext of the page, None when there's no such page.
+pub fn page(url: &Url) -> Option<String> {
+    let name = match url.path() {
+        "" => "about",
+        name => name,
+    };
+
+    PAGES
+        .iter()
+        .find(|page| page.name == name)
+        .map(|page| (page.content)())
+}
+
+fn index() -> String {
+    let mut content = String::from("# About\n\n");
+    for page in PAGES.iter() {
+        content.push_str(&format!("=> about:{} {}\n", page.name, page.description));
+    }
+    content
+}
+
+fn bookmarks_page() -> String {
+    format!("# Bookmarks\n\n{}", bookmarks::content())
+}
+
+fn downloads_page() -> String {
+    format!("# Downloads\n\n{}", downloads::content())
+}
+
+fn feeds_page() -> String {
+    let mut content = String::from("# Feeds\n\n");
+    let feeds = subscriptions::feeds();
+    if feeds.is_empty() {
+        content.push_str("No subscriptions yet. Visit a gemlog or an Atom/RSS feed and click the feed icon of the URL bar to subscribe.\n");
+        return content;
+    }
+
+    let entries = subscriptions::entries();
+    let unread = entries.iter().filter(|(_, _, read)| !read).count();
+    content.push_str(&format!("{} unread of {} entries", unread, entries.len()));
+    match subscriptions::last_refresh().and_then(|time| time.elapsed().ok()) {
+        Some(elapsed) => content.push_str(&format!(
+            ", refreshed {} minutes ago.\n",
+            elapsed.as_secs() / 60
+        )),
+        None => content.push_str(", refreshing.\n"),
+    }
+
+    let mut date = None;
+    for (feed, entry, read) in entries {
+        if date != Some(entry.date.clone()) {
+            let heading = if entry.date.is_empty() {
+                "Undated"
+            } else {
+                &entry.date
+            };
+            content.push_str(&format!("\n## {}\n", heading));
+            date = Some(entry.date.clone());
+        }
+        content.push_str(&entry_link(&feed, &entry, read));
+    }
+
+    content.push_str("\n## Subscriptions\n");
+    for (url, feed) in feeds {
+        content.push_str(&subscription_link(&url, feed));
+    }
+    content
+}
+
+// Titles come from the feeds, one on several lines would add lines to the page.
+fn entry_link(feed: &str, entry: &Entry, read: bool) -> String {
+    let new = if read { "" } else { " (new)" };
+    format!(
+        "=> {} {}: {}{}\n",
+        entry.url,
+        feeds::one_line(feed),
+        feeds::one_line(&entry.title),
+        new
+    )
+}
+
+fn subscription_link(url: &Url, feed: Option<Result<Feed, String>>) -> String {
+    match feed {
+        Some(Ok(feed)) => format!("=> {} {}\n", url, feeds::one_line(&feed.title)),
+        Some(Err(e)) => format!(
+            "=> {} {} (failed: {})\n",
+            url,
+            url,
+            e.lines().next().unwrap_or("")
+        ),
+        None => format!("=> {} {}\n", url, url),
+    }
+}
+
+fn certificates() -> String {
+    let mut content = String::from("# Client certificates\n\n");
+    content.push_str(
+        "A certificate is sent to a host when both HOST.crt and HOST.key are in your home directory.\n\n",
+    );
+
+    let hosts = certificate::hosts();
+    if hosts.is_empty() {
+        content.push_str("No certificates found.\n");
+    }
+    for host in hosts {
+        content.push_str(&format!("## {}\n", host));
+        match certificate::describe(&host) {
+            Ok(description) => content.push_str(&description),
+            Err(e) => content.push_str(&format!("Unreadable certificate: {}\n", e)),
+        }
+        content.push_str(&format!("=> gemini://{}/ Visit {}\n\n", host, host));
+    }
+    content
+}
+
+fn settings_page() -> String {
+    let mut content = String::from("# Settings\n\n");
+    content.push_str(&format!("Read from {}\n\n", settings::path().display()));
+
+    let entries = settings::effective();
+    let width = entries
+        .iter()
+        .map(|(key, _, _)| key.len())
+        .max()
+        .unwrap_or(0);
+
+    content.push_str("```\n");
+    for (key, value, from_file) in entries {
+        let source = if from_file {
+            "settings.toml"
+        } else {
+            "default"
+        };
+        content.push_str(&format!(
+            "{:width$}  {}  ({})\n",
+            key,
+            value,
+            source,
+            width = width
+        ));
+    }
+    content.push_str("```\n");
+    content
+}
+
+fn help() -> String {
+    String::from(
+        "# Help
+
+## Toolbar
+* Back and forward go through the pages visited, the back button of the mouse goes back too
+* Refresh loads the page again
+* The buttons after the URL bar add the page to the bookmarks and show them (about:bookmarks)
+* The last button edits the page over Titan, when the server allows it
+* The feed icon at the end of the URL bar subscribes to the page, or unsubscribes from it
+
+## URL bar
+* Enter a URL and press Enter to visit it
+* Without a scheme, gemini:// is used
+* file:// URLs and paths starting with / open local files
+* about: pages are generated by Castor
+
+## Feeds
+* Gemlogs with dated links (=> URL YYYY-MM-DD Title) and Atom/RSS feeds can be subscribed to
+* about:feeds lists their entries, newest first, they are refreshed in the background
+
+## Pages
+* Links to pages Castor can't display are opened with the default application
+* Input requests and Gopher searches open a dialog
+* Gopher+ items have an info button listing their attributes and views
+* Responses that can't be displayed are offered for download
+
+=> about:about All about: pages
+",
+    )
+}
+
+#[test]
+fn test_page() {
+    let url = Url::parse("about:help").unwrap();
+    assert!(page(&url).unwrap().starts_with("# Help"));
+
+    let url = Url::parse("about:").unwrap();
+    let index = page(&url).unwrap();
+    for name in &["bookmarks", "certificates", "feeds", "help", "settings"] {
+        assert!(index.contains(&format!("=> about:{} ", name)));
+    }
+
+    let url = Url::parse("about:nothing").unwrap();
+    assert!(page(&url).is_none());
+}
+
+#[test]
+fn test_feed_titles_on_several_lines() {
+    let url = Url::parse("gemini://typed-hole.org/gemlog/").unwrap();
+    let entry = Entry {
+        url: url.join("castor.gmi").unwrap(),
+        title: String::from("Castor 0.8\n```"),
+        date: String::from("2020-05-17"),
+        summary: String::new(),
+    };
+    assert_eq!(
+        entry_link("Typed\n# hole", &entry, false),
+        "=> gemini://typed-hole.org/gemlog/castor.gmi Typed # hole: Castor 0.8 ``` (new)\n"
+    );
+
+    let feed = Feed {
+        title: String::from("Typed\n=> gemini://elsewhere/ hole"),
+        entries: vec![entry],
+    };
+    assert_eq!(
+        subscription_link(&url, Some(Ok(feed))),
+        "=> gemini://typed-hole.org/gemlog/ Typed => gemini://elsewhere/ hole\n"
+    );
+}
@@ -0,0 +1,438 @@
+// Headless mode: fetches a URL, prints the response and exits, without GTK.
+//
+//   castor --dump URL [--raw] [--header-only] [--identity NAME]
+//
+// Exits with 0 on success, 1 when the server can't be reached or answers
+// nonsense, 2 on bad usage, 3 for a Gopher error item and with the status
+// code itself for any other Gemini response (10, 44, 51, 60...).
+use std::io::{Read, Write};
+use std::str::FromStr;
+use url::Url;
+
+use crate::charset::{self, Charset};
+use crate::client::Stream;
+use crate::protocols::{Finger, Gemini, Gopher};
+
+const OK: i32 = 0;
+const FAILED: i32 = 1;
+const USAGE: i32 = 2;
+const GOPHER_ERROR: i32 = 3;
+
+const MAX_REDIRECTS: usize = 5;
+
+const HELP: &str = "Usage: castor [URL]
+       castor --dump URL [--raw] [--header-only] [--identity NAME]
+
+  --dump URL        print the page at URL and exit, without opening a window
+  --raw             print the body as received instead of rendering it
+  --header-only     print the response header and skip the body
+  --identity NAME   send ~/NAME.crt and ~/NAME.key as client certificate";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub url: String,
+    pub raw: bool,
+    pub header_only: bool,
+    pub identity: Option<String>,
+}
+
+// None when Castor should open its window, the arguments are then a URL.
+pub fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+    let mut dump = false;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dump" => {
+                dump = true;
+                options.url = args.next().ok_or("--dump needs a URL")?.to_string();
+            }
+            "--raw" => options.raw = true,
+            "--header-only" => options.header_only = true,
+            "--identity" => {
+                let name = args.next().ok_or("--identity needs a name")?;
+                options.identity = Some(name.to_string());
+            }
+            "--help" | "-h" => return Err(String::new()),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ if dump => return Err(format!("Unexpected argument {}", arg)),
+            _ => (),
+        }
+    }
+
+    if dump {
+        Ok(Some(options))
+    } else if options != Options::default() {
+        Err(String::from(
+            "--raw, --header-only and --identity need --dump",
+        ))
+    } else {
+        Ok(None)
+    }
+}
+
+// Prints why the arguments were refused, returns the exit status.
+pub fn usage(error: &str) -> i32 {
+    if error.is_empty() {
+        println!("{}", HELP);
+        return OK;
+    }
+    eprintln!("{}\n\n{}", error, HELP);
+    USAGE
+}
+
+// Fetches and prints the page, returns the exit status.
+pub fn run(options: &Options) -> i32 {
+    if let Some(name) = &options.identity {
+        if let Err(e) = crate::gemini::certificate::set_identity(name) {
+            eprintln!("{}", e);
+            return USAGE;
+        }
+    }
+
+    let url = match Url::parse(&options.url) {
+        Ok(url) => url,
+        Err(url::ParseError::RelativeUrlWithoutBase) => {
+            match Url::parse(&format!("gemini://{}", options.url)) {
+                Ok(url) => url,
+                Err(e) => return failed(&e.to_string()),
+            }
+        }
+        Err(e) => return failed(&e.to_string()),
+    };
+
+    let result = match url.scheme() {
+        "about" => about(&url),
+        "gemini" => gemini(url, options),
+        "gopher" | "gophers" => gopher(&url, options),
+        "finger" => finger(&url, options),
+        scheme => {
+            eprintln!("Unsupported scheme {}", scheme);
+            return USAGE;
+        }
+    };
+
+    match result {
+        Ok(status) => status,
+        Err(e) => failed(&e),
+    }
+}
+
+fn failed(error: &str) -> i32 {
+    eprintln!("{}", error);
+    FAILED
+}
+
+fn about(url: &Url) -> Result<i32, String> {
+    let content = crate::about::page(url).ok_or(format!("No such page: {}", url))?;
+    print(&gemtext(url, &content))?;
+    Ok(OK)
+}
+
+fn gemini(mut url: Url, options: &Options) -> Result<i32, String> {
+    for _ in 0..=MAX_REDIRECTS {
+        let (meta, body) = crate::gemini::client::get_stream(Gemini {
+            source: url.to_string(),
+        })?;
+        let header = String::from_utf8_lossy(&meta.unwrap_or_default()).to_string();
+        let code: i32 = header
+            .get(..2)
+            .and_then(|code| code.parse().ok())
+            .ok_or(format!("Invalid response: {}", header.trim()))?;
+        let meta = header[2..].trim();
+
+        if options.header_only {
+            print(header.trim_end())?;
+        }
+
+        match code {
+            20..=29 => {
+                if !options.header_only {
+                    let data = read_all(body)?;
+                    let mime = meta.split(';').next().unwrap_or("").trim();
+                    if options.raw || !mime.starts_with("text/") {
+                        write(&data)?;
+                    } else if mime == "text/gemini" {
+                        print(&gemtext(&url, &String::from_utf8_lossy(&data)))?;
+                    } else {
+                        print(&String::from_utf8_lossy(&data))?;
+                    }
+                }
+                return Ok(OK);
+            }
+            30..=39 => {
+                url = url.join(meta).map_err(|e| e.to_string())?;
+            }
+            _ => {
+                if !options.header_only {
+                    eprintln!("{}", header.trim());
+                }
+                return Ok(code);
+            }
+        }
+    }
+    Err(String::from("Too many redirects"))
+}
+
+fn gopher(url: &Url, options: &Options) -> Result<i32, String> {
+    let (_meta, body) = crate::gopher::client::get_stream(Gopher {
+        source: url.to_string(),
+    })?;
+    // no header in Gopher, reaching the server is all there is to check
+    if options.header_only {
+        return Ok(OK);
+    }
+
+    let data = read_all(body)?;
+    let address = crate::gopher::address::parse(url);
+    let is_menu = address.item_type == '1' || address.item_type == '7';
+    let content = decode(url, &data);
+
+    if options.raw || !(is_menu || address.item_type == '0') {
+        write(&data)?;
+    } else if is_menu {
+        print(&gopher_menu(&content))?;
+    } else {
+        print(&content)?;
+    }
+
+    let error = is_menu
+        && crate::gopher::parser::parse(content)
+            .iter()
+            .any(|el| matches!(el, Ok(crate::gopher::parser::TextElement::Error(_))));
+    Ok(if error { GOPHER_ERROR } else { OK })
+}
+
+fn finger(url: &Url, options: &Options) -> Result<i32, String> {
+    let (_meta, body) = crate::finger::client::get_stream(Finger {
+        source: url.to_string(),
+    })?;
+    if options.header_only {
+        return Ok(OK);
+    }
+
+    let data = read_all(body)?;
+    if options.raw {
+        write(&data)?;
+    } else {
+        print(&decode(url, &data))?;
+    }
+    Ok(OK)
+}
+
+fn read_all(mut body: Stream) -> Result<Vec<u8>, String> {
+    let mut data = vec![];
+    body.read_to_end(&mut data)
+        .map_err(|e| format!("Failed to read response\n{}", e))?;
+    Ok(data)
+}
+
+// Gopher and finger pages use the charset set for their host or the guessed one.
+fn decode(url: &Url, data: &[u8]) -> String {
+    let charset = url
+        .host_str()
+        .and_then(crate::settings::charset)
+        .and_then(|name| Charset::from_name(&name))
+        .or_else(|| charset::detect(data))
+        .unwrap_or(Charset::Utf8);
+    charset::decode(data, charset)
+}
+
+fn print(text: &str) -> Result<(), String> {
+    let mut text = text.to_string();
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    write(text.as_bytes())
+}
+
+// A closed pipe (castor --dump ... | head) is not an error.
+fn write(data: &[u8]) -> Result<(), String> {
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    match stdout.write_all(data).and_then(|_| stdout.flush()) {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+// Gemtext as plain text: headings are underlined, links show their absolute
+// URL after the label and preformatted lines are kept as they are.
+pub fn gemtext(base: &Url, content: &str) -> String {
+    use crate::gemini::parser::TextElement;
+
+    let mut text = String::new();
+    let mut preformatted = false;
+
+    for line in content.lines() {
+        if line.starts_with("```") {
+            preformatted = !preformatted;
+            continue;
+        }
+        if preformatted {
+            text.push_str(line);
+            text.push('\n');
+            continue;
+        }
+
+        // prompts are only Spartan's
+        let element = if base.scheme() == "spartan" {
+            crate::gemini::parser::spartan_line(line)
+        } else {
+            TextElement::from_str(line)
+        };
+        let line = match element {
+            Ok(TextElement::H1(header)) => underline(&header, '='),
+            Ok(TextElement::H2(header)) => underline(&header, '-'),
+            Ok(TextElement::H3(header)) => header,
+            Ok(TextElement::ListItem(item)) => format!("• {}", item),
+            Ok(TextElement::Quote(quote)) => format!("> {}", quote),
+            Ok(TextElement::LinkItem(link)) => gemini_link(base, &link),
+            Ok(TextElement::PromptItem(prompt)) => {
+                gemini_link(base, &prompt.replacen("=:", "=>", 1))
+            }
+            Ok(TextElement::Text(line)) | Ok(TextElement::MonoText(line)) => line,
+            Err(_) => line.to_string(),
+        };
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+fn underline(header: &str, c: char) -> String {
+    let width = header.chars().count();
+    format!("{}\n{}", header, c.to_string().repeat(width))
+}
+
+fn gemini_link(base: &Url, line: &str) -> String {
+    use crate::gemini::link::Link;
+
+    let (url, label) = match Link::from_str(line) {
+        Ok(Link::Relative(url, label)) => match base.join(&url) {
+            Ok(url) => (url.to_string(), label),
+            Err(_) => (url, label),
+        },
+        Ok(Link::About(url, label))
+        | Ok(Link::File(url, label))
+        | Ok(Link::Finger(url, label))
+        | Ok(Link::Gemini(url, label))
+        | Ok(Link::Gopher(url, label))
+        | Ok(Link::Http(url, label))
+        | Ok(Link::Nex(url, label))
+        | Ok(Link::Spartan(url, label))
+        | Ok(Link::Email(url, label))
+        | Ok(Link::IRC(url, label))
+        | Ok(Link::XMPP(url, label))
+        | Ok(Link::Unknown(url, label)) => (url.to_string(), label),
+        Err(_) => return line.to_string(),
+    };
+    link_line(&url, &label)
+}
+
+fn link_line(url: &str, label: &str) -> String {
+    if label.is_empty() || label == url {
+        format!("<{}>", url)
+    } else {
+        format!("{} <{}>", label, url)
+    }
+}
+
+// Menus as plain text, items show their URL after the label.
+fn gopher_menu(content: &str) -> String {
+    use crate::gopher::link::Link;
+    use crate::gopher::parser::TextElement;
+
+    // the menu ends with a lone "."
+    let content: Vec<&str> = content.lines().take_while(|line| *line != ".").collect();
+
+    let mut text = String::new();
+    for el in crate::gopher::parser::parse(content.join("\n")) {
+        let line = match el {
+            Ok(TextElement::Text(line)) => crate::colors::colors::strip(&line),
+            Ok(TextElement::Error(line)) => format!("⚠ {}", line),
+            Ok(TextElement::LinkItem(line))
+            | Ok(TextElement::ExternalLinkItem(line))
+            | Ok(TextElement::Image(line))
+            | Ok(TextElement::Binary(line))
+            | Ok(TextElement::Search(line)) => {
+                if !line.contains('\t') {
+                    // plain text with a URL in it
+                    crate::colors::colors::strip(&line)
+                } else {
+                    match Link::from_str(&line) {
+                        Ok(Link::File(url, label, _))
+                        | Ok(Link::Ftp(url, label))
+                        | Ok(Link::Finger(url, label))
+                        | Ok(Link::Gemini(url, label))
+                        | Ok(Link::Gopher(url, label))
+                        | Ok(Link::Http(url, label))
+                        | Ok(Link::Image(url, label))
+                        | Ok(Link::Search(url, label))
+                        | Ok(Link::Telnet(url, label))
+                        | Ok(Link::Unknown(url, label)) => link_line(url.as_str(), &label),
+                        Ok(Link::Relative(url, label)) => link_line(&url, &label),
+                        Err(_) => line,
+                    }
+                }
+            }
+            Err(_) => continue,
+        };
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+#[test]
+fn test_parse_args() {
+    let args = |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+
+    assert_eq!(parse_args(&args(&[])), Ok(None));
+    assert_eq!(parse_args(&args(&["gemini://example.org"])), Ok(None));
+    assert_eq!(
+        parse_args(&args(&[
+            "--raw",
+            "--dump",
+            "gemini://example.org",
+            "--identity",
+            "me"
+        ])),
+        Ok(Some(Options {
+            url: String::from("gemini://example.org"),
+            raw: true,
+            header_only: false,
+            identity: Some(String::from("me")),
+        }))
+    );
+    assert!(parse_args(&args(&["--dump"])).is_err());
+    assert!(parse_args(&args(&["--raw", "gemini://example.org"])).is_err());
+    assert!(parse_args(&args(&["--dump", "gemini://example.org", "--verbose"])).is_err());
+}
+
+#[test]
+fn test_gemtext() {
+    let base = Url::parse("gemini://example.org/gemlog/").unwrap();
+    let content = "# Title\n\
+                   text\n\
+                   * item\n\
+                   => post.gmi A post\n\
+                   => gemini://example.org/\n\
+                   ```\n\
+                   # not a heading\n\
+                   ```\n\
+                   > quote\n\
+                   =: search Search";
+    assert_eq!(
+        gemtext(&base, content),
+        "Title\n=====\n\
+         text\n\
+         • item\n\
+         A post <gemini://example.org/gemlog/post.gmi>\n\
+         <gemini://example.org/>\n\
+         # not a heading\n\
+         > quote\n\
+         =: search Search\n"
+    );
+}
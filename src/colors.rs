@@ -1,317 +1,431 @@
 pub mod colors {
-    use ansi_parser::AnsiSequence;
-    use ansi_parser::{AnsiParser, Output};
+    // xterm colours for SGR 30-37 and 90-97, `ansi` in the `[colors]`
+    // section of the settings replaces them.
+    pub const DEFAULT_PALETTE: [&str; 16] = [
+        "#000000", "#cd0000", "#00cd00", "#cdcd00", "#0000ee", "#cd00cd", "#00cdcd", "#e5e5e5",
+        "#7f7f7f", "#ff0000", "#00ff00", "#ffff00", "#5c5cff", "#ff00ff", "#00ffff", "#ffffff",
+    ];
 
-    pub fn cleanup(line: &str) -> String {
-        let parsed: Vec<Output> = line.ansi_parse().collect();
-        let mut s = String::new();
+    // Cursor moves are turned into spaces, capped to keep lines sane.
+    const MAX_SKIP: usize = 256;
 
-        for p in parsed {
-            match p {
-                Output::TextBlock(text) => {
-                    let clean_text = glib::markup_escape_text(&text);
-                    s.push_str(&clean_text)
-                }
-                _ => s.push_str(""),
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Color {
+        Indexed(u8),
+        Rgb(u8, u8, u8),
+    }
+
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    struct Style {
+        foreground: Option<Color>,
+        background: Option<Color>,
+        bold: bool,
+        italic: bool,
+        underline: bool,
+        reverse: bool,
+        strikethrough: bool,
+    }
+
+    enum Token {
+        Text(String),
+        Sgr(Vec<u32>),
+        Skip(usize),
+        End,
+    }
+
+    // SGR state machine, the style carries over from one line to the next
+    // like in a terminal.
+    pub struct Ansi {
+        style: Style,
+        palette: Vec<String>,
+        ended: bool,
+    }
+
+    impl Ansi {
+        pub fn new() -> Ansi {
+            let palette = match crate::settings::ansi_palette() {
+                Some(palette) if palette.len() == 16 => palette,
+                _ => DEFAULT_PALETTE.iter().map(|c| c.to_string()).collect(),
+            };
+            Ansi::with_palette(palette)
+        }
+
+        pub fn with_palette(palette: Vec<String>) -> Ansi {
+            Ansi {
+                style: Style::default(),
+                palette,
+                ended: false,
             }
         }
-        s
-    }
 
-    pub fn colorize(line: &str) -> String {
-        let parsed: Vec<Output> = line.ansi_parse().collect();
+        // Escaped Pango markup for a line, every span opened is closed.
+        pub fn markup(&mut self, line: &str) -> String {
+            let mut markup = String::new();
+            if self.ended {
+                return markup;
+            }
 
-        let mut s = String::new();
-        let mut span_opened = 0;
-
-        for p in parsed {
-            match p {
-                Output::Escape(AnsiSequence::SetGraphicsMode(colors)) => {
-                    if colors.len() == 1 {
-                        s.push_str("");
-                    } else if colors[0] == 38 {
-                        let color = colors.last().unwrap();
-                        span_opened += 1;
-                        s.push_str(&format!(
-                            "<span foreground={:?}>",
-                            ansi_color_to_hex(*color)
-                        ))
-                    } else {
-                        let color = colors.last().unwrap();
-                        span_opened += 1;
-                        s.push_str(&format!(
-                            "<span background={:?}>",
-                            ansi_color_to_hex(*color)
-                        ))
+            for token in tokenize(line) {
+                match token {
+                    Token::Text(text) => markup.push_str(&self.span(&text)),
+                    Token::Sgr(params) => self.apply(&params),
+                    Token::Skip(count) => markup.push_str(&" ".repeat(count)),
+                    Token::End => {
+                        self.ended = true;
+                        break;
                     }
                 }
-                Output::TextBlock(text) => {
-                    let clean_text = glib::markup_escape_text(&text);
-                    if span_opened > 0 {
-                        let spans = "</span>".repeat(span_opened);
-                        span_opened = 0;
-                        s.push_str(&format!("{}{}", clean_text, spans))
-                    } else {
-                        s.push_str(&clean_text)
+            }
+            markup
+        }
+
+        fn apply(&mut self, params: &[u32]) {
+            if params.is_empty() {
+                self.style = Style::default();
+                return;
+            }
+
+            let mut i = 0;
+            while i < params.len() {
+                match params[i] {
+                    0 => self.style = Style::default(),
+                    1 => self.style.bold = true,
+                    3 => self.style.italic = true,
+                    4 => self.style.underline = true,
+                    7 => self.style.reverse = true,
+                    9 => self.style.strikethrough = true,
+                    21 | 22 => self.style.bold = false,
+                    23 => self.style.italic = false,
+                    24 => self.style.underline = false,
+                    27 => self.style.reverse = false,
+                    29 => self.style.strikethrough = false,
+                    n @ 30..=37 => self.style.foreground = Some(Color::Indexed((n - 30) as u8)),
+                    39 => self.style.foreground = None,
+                    n @ 40..=47 => self.style.background = Some(Color::Indexed((n - 40) as u8)),
+                    49 => self.style.background = None,
+                    n @ 90..=97 => self.style.foreground = Some(Color::Indexed((n - 90 + 8) as u8)),
+                    n @ 100..=107 => {
+                        self.style.background = Some(Color::Indexed((n - 100 + 8) as u8))
                     }
+                    n @ 38 | n @ 48 => {
+                        let (color, used) = extended_color(&params[i + 1..]);
+                        if color.is_some() {
+                            if n == 38 {
+                                self.style.foreground = color;
+                            } else {
+                                self.style.background = color;
+                            }
+                        }
+                        i += used;
+                    }
+                    _ => (),
+                }
+                i += 1;
+            }
+        }
+
+        fn span(&self, text: &str) -> String {
+            let text = escape(text);
+            if text.is_empty() || self.style == Style::default() {
+                return text;
+            }
+
+            let style = &self.style;
+            // bold makes the 8 basic colours bright, ANSI art relies on it
+            let foreground = match style.foreground {
+                Some(Color::Indexed(n)) if style.bold && n < 8 => Some(Color::Indexed(n + 8)),
+                color => color,
+            };
+            let (foreground, background) = if style.reverse {
+                (
+                    style.background.or(Some(Color::Indexed(0))),
+                    foreground.or(Some(Color::Indexed(7))),
+                )
+            } else {
+                (foreground, style.background)
+            };
+
+            let mut attributes = String::new();
+            if let Some(color) = foreground {
+                attributes.push_str(&format!(" foreground=\"{}\"", self.hex(color)));
+            }
+            if let Some(color) = background {
+                attributes.push_str(&format!(" background=\"{}\"", self.hex(color)));
+            }
+            if style.bold {
+                attributes.push_str(" weight=\"bold\"");
+            }
+            if style.italic {
+                attributes.push_str(" style=\"italic\"");
+            }
+            if style.underline {
+                attributes.push_str(" underline=\"single\"");
+            }
+            if style.strikethrough {
+                attributes.push_str(" strikethrough=\"true\"");
+            }
+
+            format!("<span{}>{}</span>", attributes, text)
+        }
+
+        fn hex(&self, color: Color) -> String {
+            match color {
+                Color::Indexed(n) if n < 16 => escape(&self.palette[n as usize]),
+                Color::Indexed(n) if n < 232 => {
+                    // 6x6x6 colour cube
+                    let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+                    let n = n - 16;
+                    rgb(level(n / 36), level((n / 6) % 6), level(n % 6))
+                }
+                Color::Indexed(n) => {
+                    let grey = 8 + (n - 232) * 10;
+                    rgb(grey, grey, grey)
                 }
-                _ => s.push_str("something else"),
+                Color::Rgb(r, g, b) => rgb(r, g, b),
+            }
+        }
+    }
+
+    impl Default for Ansi {
+        fn default() -> Self {
+            Ansi::new()
+        }
+    }
+
+    // 38 and 48 are followed by "5;n" for the 256 colours or "2;r;g;b",
+    // also tells how many parameters were used.
+    fn extended_color(params: &[u32]) -> (Option<Color>, usize) {
+        match params.first() {
+            Some(5) => match params.get(1) {
+                Some(n) if *n < 256 => (Some(Color::Indexed(*n as u8)), 2),
+                _ => (None, params.len().min(2)),
+            },
+            Some(2) if params.len() >= 4 => {
+                let channel = |v: u32| v.min(255) as u8;
+                let color = Color::Rgb(channel(params[1]), channel(params[2]), channel(params[3]));
+                (Some(color), 4)
+            }
+            _ => (None, params.len()),
+        }
+    }
+
+    fn rgb(r: u8, g: u8, b: u8) -> String {
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('\'', "&#39;")
+            .replace('"', "&quot;")
+    }
+
+    // Splits a line into text and escape sequences. Only SGR and cursor
+    // forward are kept, other sequences are dropped. ANSI art files end with
+    // a SUB character followed by their SAUCE record, which is not shown.
+    fn tokenize(line: &str) -> Vec<Token> {
+        let mut tokens = vec![];
+        let mut text = String::new();
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c == '\x1a' {
+                if !text.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut text)));
+                }
+                tokens.push(Token::End);
+                return tokens;
+            }
+            if c != '\x1b' {
+                text.push(c);
+                continue;
+            }
+            if !text.is_empty() {
+                tokens.push(Token::Text(std::mem::take(&mut text)));
+            }
+
+            if chars.peek() != Some(&'[') {
+                // two characters sequence
+                chars.next();
+                continue;
+            }
+            chars.next();
+
+            let mut params = String::new();
+            let mut command = None;
+            for p in &mut chars {
+                if ('\x40'..='\x7e').contains(&p) {
+                    command = Some(p);
+                    break;
+                }
+                params.push(p);
+            }
+
+            match command {
+                Some('m') => {
+                    let params = params
+                        .split(';')
+                        .filter(|_| !params.is_empty())
+                        .map(|p| p.parse().unwrap_or(0))
+                        .collect();
+                    tokens.push(Token::Sgr(params));
+                }
+                Some('C') => {
+                    let count = params.parse().unwrap_or(1);
+                    tokens.push(Token::Skip(count.clamp(1, MAX_SKIP)));
+                }
+                _ => (),
+            }
+        }
+        if !text.is_empty() {
+            tokens.push(Token::Text(text));
+        }
+        tokens
+    }
+
+    // Escaped text without the escape sequences.
+    pub fn cleanup(line: &str) -> String {
+        let mut s = String::new();
+
+        for token in tokenize(line) {
+            match token {
+                Token::Text(text) => s.push_str(&escape(&text)),
+                Token::Skip(count) => s.push_str(&" ".repeat(count)),
+                Token::Sgr(_) => (),
+                Token::End => break,
+            }
+        }
+        s
+    }
+
+    // Plain text without the escape sequences.
+    pub fn strip(line: &str) -> String {
+        let mut s = String::new();
+
+        for token in tokenize(line) {
+            match token {
+                Token::Text(text) => s.push_str(&text),
+                Token::Skip(count) => s.push_str(&" ".repeat(count)),
+                Token::Sgr(_) => (),
+                Token::End => break,
             }
         }
         s
     }
 
-    fn ansi_color_to_hex(color: u32) -> &'static str {
-        match color {
-            7  => "#dadada",
-            8  => "#6c6c6c",
-            10 => "#00ff00",
-            11 => "#ffff00",
-            12 => "#0000ff",
-            13 => "#ff00ff",
-            14 => "#00ffff",
-            15 => "#ffffff",
-            16 => "#000000",
-            17 => "#00005f",
-            18 => "#000087",
-            19 => "#0000af",
-            20 => "#0000d7",
-            21 => "#0000ff",
-            22 => "#005f00",
-            23 => "#005f5f",
-            24 => "#005f87",
-            25 => "#005faf",
-            26 => "#005fd7",
-            27 => "#005fff",
-            28 => "#008700",
-            29 => "#00875f",
-            30 => "#008787",
-            31 => "#0087af",
-            32 => "#0087d7",
-            33 => "#0087ff",
-            34 => "#00af00",
-            35 => "#00af5f",
-            36 => "#00af87",
-            37 => "#00afaf",
-            38 => "#00afd7",
-            39 => "#00afff",
-            40 => "#00d700",
-            41 => "#00d75f",
-            42 => "#00d787",
-            43 => "#00d7af",
-            44 => "#00d7d7",
-            45 => "#00d7ff",
-            46 => "#00ff00",
-            47 => "#00ff5f",
-            48 => "#00ff87",
-            49 => "#00ffaf",
-            50 => "#00ffd7",
-            51 => "#00ffff",
-            52 => "#5f0000",
-            53 => "#5f005f",
-            54 => "#5f0087",
-            55 => "#5f00af",
-            56 => "#5f00d7",
-            57 => "#5f00ff",
-            58 => "#5f5f00",
-            59 => "#5f5f5f",
-            60 => "#5f5f87",
-            61 => "#5f5faf",
-            62 => "#5f5fd7",
-            63 => "#5f5fff",
-            64 => "#5f8700",
-            65 => "#5f875f",
-            66 => "#5f8787",
-            67 => "#5f87af",
-            68 => "#5f87d7",
-            69 => "#5f87ff",
-            70 => "#5faf00",
-            71 => "#5faf5f",
-            72 => "#5faf87",
-            73 => "#5fafaf",
-            74 => "#5fafd7",
-            75 => "#5fafff",
-            76 => "#5fd700",
-            77 => "#5fd75f",
-            78 => "#5fd787",
-            79 => "#5fd7af",
-            80 => "#5fd7d7",
-            81 => "#5fd7ff",
-            82 => "#5fff00",
-            83 => "#5fff5f",
-            84 => "#5fff87",
-            85 => "#5fffaf",
-            86 => "#5fffd7",
-            87 => "#5fffff",
-            88 => "#870000",
-            89 => "#87005f",
-            90 => "#870087",
-            91 => "#8700af",
-            92 => "#8700d7",
-            93 => "#8700ff",
-            94 => "#875f00",
-            95 => "#875f5f",
-            96 => "#875f87",
-            97 => "#875faf",
-            98 => "#875fd7",
-            99 => "#875fff",
-            100 => "#878700",
-            101 => "#87875f",
-            102 => "#878787",
-            103 => "#8787af",
-            104 => "#8787d7",
-            105 => "#8787ff",
-            106 => "#87af00",
-            107 => "#87af5f",
-            108 => "#87af87",
-            109 => "#87afaf",
-            110 => "#87afd7",
-            111 => "#87afff",
-            112 => "#87d700",
-            113 => "#87d75f",
-            114 => "#87d787",
-            115 => "#87d7af",
-            116 => "#87d7d7",
-            117 => "#87d7ff",
-            118 => "#87ff00",
-            119 => "#87ff5f",
-            120 => "#87ff87",
-            121 => "#87ffaf",
-            122 => "#87ffd7",
-            123 => "#87ffff",
-            124 => "#af0000",
-            125 => "#af005f",
-            126 => "#af0087",
-            127 => "#af00af",
-            128 => "#af00d7",
-            129 => "#af00ff",
-            130 => "#af5f00",
-            131 => "#af5f5f",
-            132 => "#af5f87",
-            133 => "#af5faf",
-            134 => "#af5fd7",
-            135 => "#af5fff",
-            136 => "#af8700",
-            137 => "#af875f",
-            138 => "#af8787",
-            139 => "#af87af",
-            140 => "#af87d7",
-            141 => "#af87ff",
-            142 => "#afaf00",
-            143 => "#afaf5f",
-            144 => "#afaf87",
-            145 => "#afafaf",
-            146 => "#afafd7",
-            147 => "#afafff",
-            148 => "#afd700",
-            149 => "#afd75f",
-            150 => "#afd787",
-            151 => "#afd7af",
-            152 => "#afd7d7",
-            153 => "#afd7ff",
-            154 => "#afff00",
-            155 => "#afff5f",
-            156 => "#afff87",
-            157 => "#afffaf",
-            158 => "#afffd7",
-            159 => "#afffff",
-            160 => "#d70000",
-            161 => "#d7005f",
-            162 => "#d70087",
-            163 => "#d700af",
-            164 => "#d700d7",
-            165 => "#d700ff",
-            166 => "#d75f00",
-            167 => "#d75f5f",
-            168 => "#d75f87",
-            169 => "#d75faf",
-            170 => "#d75fd7",
-            171 => "#d75fff",
-            172 => "#d78700",
-            173 => "#d7875f",
-            174 => "#d78787",
-            175 => "#d787af",
-            176 => "#d787d7",
-            177 => "#d787ff",
-            178 => "#d7af00",
-            179 => "#d7af5f",
-            180 => "#d7af87",
-            181 => "#d7afaf",
-            182 => "#d7afd7",
-            183 => "#d7afff",
-            184 => "#d7d700",
-            185 => "#d7d75f",
-            186 => "#d7d787",
-            187 => "#d7d7af",
-            188 => "#d7d7d7",
-            189 => "#d7d7ff",
-            190 => "#d7ff00",
-            191 => "#d7ff5f",
-            192 => "#d7ff87",
-            193 => "#d7ffaf",
-            194 => "#d7ffd7",
-            195 => "#d7ffff",
-            196 => "#ff0000",
-            197 => "#ff005f",
-            198 => "#ff0087",
-            199 => "#ff00af",
-            200 => "#ff00d7",
-            201 => "#ff00ff",
-            202 => "#ff5f00",
-            203 => "#ff5f5f",
-            204 => "#ff5f87",
-            205 => "#ff5faf",
-            206 => "#ff5fd7",
-            207 => "#ff5fff",
-            208 => "#ff8700",
-            209 => "#ff875f",
-            210 => "#ff8787",
-            211 => "#ff87af",
-            212 => "#ff87d7",
-            213 => "#ff87ff",
-            214 => "#ffaf00",
-            215 => "#ffaf5f",
-            216 => "#ffaf87",
-            217 => "#ffafaf",
-            218 => "#ffafd7",
-            219 => "#ffafff",
-            220 => "#ffd700",
-            221 => "#ffd75f",
-            222 => "#ffd787",
-            223 => "#ffd7af",
-            224 => "#ffd7d7",
-            225 => "#ffd7ff",
-            226 => "#ffff00",
-            227 => "#ffff5f",
-            228 => "#ffff87",
-            229 => "#ffffaf",
-            230 => "#ffffd7",
-            231 => "#ffffff",
-            232 => "#080808",
-            233 => "#121212",
-            234 => "#1c1c1c",
-            235 => "#262626",
-            236 => "#303030",
-            237 => "#3a3a3a",
-            238 => "#444444",
-            239 => "#4e4e4e",
-            240 => "#585858",
-            241 => "#626262",
-            242 => "#6c6c6c",
-            243 => "#767676",
-            244 => "#808080",
-            245 => "#8a8a8a",
-            246 => "#949494",
-            247 => "#9e9e9e",
-            248 => "#a8a8a8",
-            249 => "#b2b2b2",
-            250 => "#bcbcbc",
-            251 => "#c6c6c6",
-            252 => "#d0d0d0",
-            253 => "#dadada",
-            254 => "#e4e4e4",
-            255 => "#eeeeee",
-            _ => "#000000",
+    #[cfg(test)]
+    fn ansi() -> Ansi {
+        Ansi::with_palette(DEFAULT_PALETTE.iter().map(|c| c.to_string()).collect())
+    }
+
+    // Opening and closing tags must pair up, Pango rejects the whole line
+    // otherwise.
+    #[cfg(test)]
+    fn assert_balanced(markup: &str) {
+        let mut depth = 0;
+        for (i, _) in markup.match_indices('<') {
+            if markup[i..].starts_with("</span>") {
+                depth -= 1;
+            } else {
+                assert!(markup[i..].starts_with("<span"), "{}", markup);
+                depth += 1;
+            }
+            assert!((0..=1).contains(&depth), "{}", markup);
+        }
+        assert_eq!(depth, 0, "{}", markup);
+    }
+
+    #[test]
+    fn test_basic_colors() {
+        let mut ansi = ansi();
+        assert_eq!(
+            ansi.markup("\x1b[31mred\x1b[0m plain"),
+            "<span foreground=\"#cd0000\">red</span> plain"
+        );
+        assert_eq!(
+            ansi.markup("\x1b[1;34mbold\x1b[22m \x1b[4;97;41mlink\x1b[m"),
+            "<span foreground=\"#5c5cff\" weight=\"bold\">bold</span>\
+             <span foreground=\"#0000ee\"> </span>\
+             <span foreground=\"#ffffff\" background=\"#cd0000\" underline=\"single\">link</span>"
+        );
+    }
+
+    #[test]
+    fn test_extended_colors() {
+        let mut ansi = ansi();
+        assert_eq!(
+            ansi.markup("\x1b[38;5;208ma\x1b[38;5;244mb\x1b[48;2;18;52;86mc"),
+            "<span foreground=\"#ff8700\">a</span>\
+             <span foreground=\"#808080\">b</span>\
+             <span foreground=\"#808080\" background=\"#123456\">c</span>"
+        );
+        // the style carries over to the next line
+        assert_eq!(
+            ansi.markup("d\x1b[0m"),
+            "<span foreground=\"#808080\" background=\"#123456\">d</span>"
+        );
+        assert_eq!(ansi.markup("e"), "e");
+    }
+
+    #[test]
+    fn test_escapes_and_unknown_sequences() {
+        let mut ansi = ansi();
+        assert_eq!(
+            ansi.markup("\x1b[2J\x1b[1;1H<b> & \x1b[3C!"),
+            "&lt;b&gt; &amp;    !"
+        );
+        assert_eq!(cleanup("\x1b[1;31m<3\x1b[0m"), "&lt;3");
+        assert_eq!(strip("\x1b[1;31m<3\x1b[0m"), "<3");
+    }
+
+    #[test]
+    fn test_sauce_record() {
+        let content = crate::charset::decode(
+            include_bytes!("../tests/data/ansi/banner.ans"),
+            crate::charset::Charset::Cp437,
+        );
+        let mut ansi = ansi();
+        let lines: Vec<String> = content.lines().map(|line| ansi.markup(line)).collect();
+
+        assert_eq!(
+            lines[0],
+            format!(
+                "<span foreground=\"#5c5cff\" weight=\"bold\">{}</span>",
+                "▄".repeat(30)
+            )
+        );
+        assert_eq!(
+            lines[1],
+            "<span background=\"#0000ee\"> </span>\
+             <span foreground=\"#ffff00\" background=\"#0000ee\" weight=\"bold\">███</span>\
+             <span foreground=\"#ffffff\" background=\"#0000ee\" weight=\"bold\">▒▓ </span>\
+             <span foreground=\"#00ffff\" background=\"#0000ee\" weight=\"bold\">C A S T O R</span>     \
+             <span foreground=\"#0000ee\" background=\"#0000ee\">░░</span>"
+        );
+        // SUB and the SAUCE record after it are dropped
+        assert_eq!(
+            lines.last().unwrap(),
+            &format!("<span foreground=\"#cd0000\">╚{}╝</span>", "═".repeat(10))
+        );
+        assert_eq!(ansi.markup("more text"), "");
+        assert_eq!(strip("end\x1aSAUCE00"), "end");
+    }
+
+    #[test]
+    fn test_ansi_art() {
+        for file in &[
+            &include_bytes!("../tests/data/ansi/banner.ans")[..],
+            &include_bytes!("../tests/data/ansi/palette.ans")[..],
+            &include_bytes!("../tests/data/ansi/truecolor.ans")[..],
+        ] {
+            let content = crate::charset::decode(file, crate::charset::Charset::Cp437);
+            let mut ansi = ansi();
+            for line in content.lines() {
+                let markup = ansi.markup(line);
+                assert!(!markup.contains('\x1b'), "{}", markup);
+                assert_balanced(&markup);
+            }
         }
     }
 }
@@ -1,11 +1,13 @@
 use gtk::prelude::*;
 use gtk::ResponseType;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use url::{Position, Url};
 
 use crate::gui::Gui;
-use crate::protocols::Gemini;
+use crate::protocols::{Gemini, Gopher, Spartan};
 
 pub fn info(gui: &Arc<Gui>, message: &str) {
     let dialog = gtk::Dialog::new_with_buttons(
@@ -41,6 +43,99 @@ pub fn error(gui: &Arc<Gui>, message: &str) {
     dialog.show_all();
 }
 
+pub fn confirm(gui: &Arc<Gui>, message: &str) -> bool {
+    let dialog = gtk::Dialog::new_with_buttons(
+        Some("Confirm"),
+        Some(gui.window()),
+        gtk::DialogFlags::MODAL,
+        &[("No", ResponseType::Reject), ("Yes", ResponseType::Accept)],
+    );
+    dialog.set_default_response(ResponseType::Accept);
+
+    let content_area = dialog.get_content_area();
+    let message = gtk::Label::new(Some(message));
+    content_area.add(&message);
+
+    dialog.show_all();
+    let confirmed = dialog.run() == ResponseType::Accept;
+    dialog.destroy();
+
+    confirmed
+}
+
+pub enum DownloadAction {
+    Save,
+    Open,
+    Cancel,
+}
+
+pub fn download(gui: &Arc<Gui>, name: &str, mime: &str, executable: bool) -> DownloadAction {
+    let buttons: &[(&str, ResponseType)] = if executable {
+        &[("Cancel", ResponseType::Cancel), ("Save", ResponseType::Accept)]
+    } else {
+        &[
+            ("Cancel", ResponseType::Cancel),
+            ("Open", ResponseType::Apply),
+            ("Save", ResponseType::Accept),
+        ]
+    };
+    let dialog = gtk::Dialog::new_with_buttons(
+        Some("Download"),
+        Some(gui.window()),
+        gtk::DialogFlags::MODAL,
+        buttons,
+    );
+    dialog.set_default_response(ResponseType::Accept);
+
+    let kind = if mime.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", mime)
+    };
+    let warning = if executable {
+        "\nThis looks like a program, Castor will not open it.\n"
+    } else {
+        ""
+    };
+    let content_area = dialog.get_content_area();
+    let message = gtk::Label::new(Some(&format!("\n{}{}\n{}", name, kind, warning)));
+    content_area.add(&message);
+
+    dialog.show_all();
+    let action = match dialog.run() {
+        ResponseType::Accept => DownloadAction::Save,
+        ResponseType::Apply => DownloadAction::Open,
+        _ => DownloadAction::Cancel,
+    };
+    dialog.destroy();
+
+    action
+}
+
+pub fn save_file(gui: &Arc<Gui>, folder: &Path, name: &str) -> Option<PathBuf> {
+    let dialog = gtk::FileChooserDialog::with_buttons(
+        Some("Save"),
+        Some(gui.window()),
+        gtk::FileChooserAction::Save,
+        &[
+            ("Cancel", ResponseType::Cancel),
+            ("Save", ResponseType::Accept),
+        ],
+    );
+    dialog.set_do_overwrite_confirmation(true);
+    dialog.set_current_folder(folder);
+    dialog.set_current_name(name);
+
+    let path = if dialog.run() == ResponseType::Accept {
+        dialog.get_filename()
+    } else {
+        None
+    };
+    dialog.destroy();
+
+    path
+}
+
 pub fn input(gui: &Arc<Gui>, url: Url, message: &str) {
     let dialog = gtk::Dialog::new_with_buttons(
         Some(message),
@@ -61,10 +156,169 @@ pub fn input(gui: &Arc<Gui>, url: Url, message: &str) {
     if dialog.run() == gtk::ResponseType::Accept {
         let response = entry.get_text().expect("get_text failed").to_string();
         let cleaned: &str = &url[..Position::AfterPath];
-        let full_url = format!("{}?{}", cleaned.to_string(), response);
 
-        crate::visit_url(&gui, Gemini { source: full_url });
+        match url.scheme() {
+            // gopher searches send the query after a tab, kept in the URL
+            // so history works
+            "gopher" | "gophers" => {
+                if let Some(full_url) = crate::gopher::address::with_search(&url, &response) {
+                    let source = full_url.to_string();
+                    crate::visit_url(&gui, Gopher { source });
+                }
+            }
+            "spartan" => {
+                let query = utf8_percent_encode(&response, NON_ALPHANUMERIC);
+                let full_url = format!("{}?{}", cleaned, query);
+                crate::visit_url(&gui, Spartan { source: full_url });
+            }
+            _ => {
+                let full_url = format!("{}?{}", cleaned, response);
+                crate::visit_url(&gui, Gemini { source: full_url });
+            }
+        }
+    }
+
+    dialog.destroy();
+}
+
+pub enum UploadAction {
+    Text,
+    File,
+    Cancel,
+}
+
+pub fn upload(gui: &Arc<Gui>, url: &Url) -> UploadAction {
+    let dialog = gtk::Dialog::new_with_buttons(
+        Some("Upload"),
+        Some(gui.window()),
+        gtk::DialogFlags::MODAL,
+        &[
+            ("Cancel", ResponseType::Cancel),
+            ("Upload a file", ResponseType::Apply),
+            ("Write text", ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_response(ResponseType::Accept);
+
+    let content_area = dialog.get_content_area();
+    let message = gtk::Label::new(Some(&format!("\nWhat do you want to send to\n{}?\n", url)));
+    content_area.add(&message);
+
+    dialog.show_all();
+    let action = match dialog.run() {
+        ResponseType::Accept => UploadAction::Text,
+        ResponseType::Apply => UploadAction::File,
+        _ => UploadAction::Cancel,
+    };
+    dialog.destroy();
+
+    action
+}
+
+// Lets the text be edited before it is uploaded, returns it with the token.
+pub fn editor(gui: &Arc<Gui>, url: &Url, text: &str) -> Option<(String, String)> {
+    let dialog = gtk::Dialog::new_with_buttons(
+        Some(url.as_str()),
+        Some(gui.window()),
+        gtk::DialogFlags::MODAL,
+        &[
+            ("Cancel", ResponseType::Cancel),
+            ("Upload", ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_size(700, 500);
+
+    let text_view = gtk::TextView::new();
+    text_view.set_monospace(true);
+    text_view.set_wrap_mode(gtk::WrapMode::WordChar);
+    let buffer = text_view.get_buffer().unwrap();
+    buffer.set_text(text);
+
+    let scrolled_window =
+        gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    scrolled_window.set_vexpand(true);
+    scrolled_window.add(&text_view);
+
+    let token = token_entry();
+    let content_area = dialog.get_content_area();
+    content_area.add(&scrolled_window);
+    content_area.add(&token);
+
+    dialog.show_all();
+    let edited = if dialog.run() == ResponseType::Accept {
+        let (start, end) = buffer.get_bounds();
+        let text = buffer
+            .get_text(&start, &end, false)
+            .map(|t| t.to_string())
+            .unwrap_or_default();
+        let token = token.get_text().map(|t| t.to_string()).unwrap_or_default();
+        Some((text, token))
+    } else {
+        None
+    };
+    dialog.destroy();
+
+    edited
+}
+
+pub fn upload_file(gui: &Arc<Gui>) -> Option<(PathBuf, String)> {
+    let dialog = gtk::FileChooserDialog::with_buttons(
+        Some("Upload"),
+        Some(gui.window()),
+        gtk::FileChooserAction::Open,
+        &[
+            ("Cancel", ResponseType::Cancel),
+            ("Upload", ResponseType::Accept),
+        ],
+    );
+    let token = token_entry();
+    dialog.set_extra_widget(&token);
+
+    let chosen = if dialog.run() == ResponseType::Accept {
+        let token = token.get_text().map(|t| t.to_string()).unwrap_or_default();
+        dialog.get_filename().map(|path| (path, token))
+    } else {
+        None
+    };
+    dialog.destroy();
+
+    chosen
+}
+
+fn token_entry() -> gtk::Entry {
+    let entry = gtk::Entry::new();
+    entry.set_placeholder_text(Some("Token (optional)"));
+    entry.set_visibility(false);
+    entry
+}
+
+// Shows Gopher+ attributes, returns the view picked when there are several.
+pub fn item_info(gui: &Arc<Gui>, attributes: &str, views: &[String]) -> Option<usize> {
+    let dialog = gtk::Dialog::new_with_buttons(
+        Some("Item info"),
+        Some(gui.window()),
+        gtk::DialogFlags::MODAL,
+        &[("Close", ResponseType::Close)],
+    );
+
+    let content_area = dialog.get_content_area();
+    let message = gtk::Label::new(Some(attributes));
+    message.set_selectable(true);
+    content_area.add(&message);
+
+    if views.len() > 1 {
+        content_area.add(&gtk::Label::new(Some("\nOpen this item as:")));
+        for (index, view) in views.iter().enumerate() {
+            dialog.add_button(view, ResponseType::Other(index as u16));
+        }
     }
 
+    dialog.show_all();
+    let view = match dialog.run() {
+        ResponseType::Other(index) => Some(index as usize),
+        _ => None,
+    };
     dialog.destroy();
+
+    view
 }
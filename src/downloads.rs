@@ -0,0 +1,307 @@
+extern crate dirs;
+
+use percent_encoding::percent_decode;
+use std::fs;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use url::Url;
+
+use crate::client::{format_size, Stream};
+
+lazy_static! {
+    static ref DOWNLOADS: Mutex<Vec<Download>> = Mutex::new(vec![]);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum State {
+    Running,
+    Finished,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Download {
+    pub url: String,
+    pub path: PathBuf,
+    pub received: u64,
+    pub state: State,
+}
+
+pub enum Progress {
+    Received(u64),
+    Finished(Result<u64, String>),
+}
+
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "app", "appimage", "apk", "bat", "bin", "cmd", "com", "command", "cpl", "deb", "desktop",
+    "dmg", "exe", "hta", "jar", "js", "lnk", "msi", "pkg", "pl", "ps1", "py", "rpm", "run", "scr",
+    "sh", "vbs", "wsf",
+];
+
+const EXECUTABLE_TYPES: &[&str] = &[
+    "application/java-archive",
+    "application/vnd.android.package-archive",
+    "application/vnd.microsoft.portable-executable",
+    "application/x-debian-package",
+    "application/x-desktop",
+    "application/x-executable",
+    "application/x-msdos-program",
+    "application/x-msdownload",
+    "application/x-rpm",
+    "application/x-sh",
+    "application/x-shellscript",
+];
+
+// ELF, PE, shebang scripts and Mach-O binaries.
+const EXECUTABLE_HEADERS: &[&[u8]] = &[
+    b"\x7fELF",
+    b"MZ",
+    b"#!",
+    b"\xca\xfe\xba\xbe",
+    b"\xcf\xfa\xed\xfe",
+    b"\xce\xfa\xed\xfe",
+];
+
+pub fn add(url: &str, path: &Path) -> usize {
+    let mut downloads = DOWNLOADS.lock().unwrap();
+    downloads.push(Download {
+        url: url.to_string(),
+        path: path.to_path_buf(),
+        received: 0,
+        state: State::Running,
+    });
+    downloads.len() - 1
+}
+
+pub fn update(id: usize, received: u64) {
+    if let Some(download) = DOWNLOADS.lock().unwrap().get_mut(id) {
+        download.received = received;
+    }
+}
+
+pub fn finish(id: usize, result: &Result<u64, String>) {
+    if let Some(download) = DOWNLOADS.lock().unwrap().get_mut(id) {
+        match result {
+            Ok(size) => {
+                download.received = *size;
+                download.state = State::Finished;
+            }
+            Err(e) => download.state = State::Failed(e.to_string()),
+        }
+    }
+}
+
+pub fn content() -> String {
+    let downloads = DOWNLOADS.lock().unwrap();
+    if downloads.is_empty() {
+        return String::from("Nothing downloaded yet.\n");
+    }
+
+    let mut content = String::new();
+    for download in downloads.iter().rev() {
+        let state = match &download.state {
+            State::Running => String::from("downloading"),
+            State::Finished => String::from("done"),
+            State::Failed(e) => format!("failed: {}", e),
+        };
+        content.push_str(&format!(
+            "* {} ({}, {})\n=> {}\n",
+            download.path.display(),
+            format_size(download.received as usize),
+            state,
+            download.url
+        ));
+    }
+    content
+}
+
+pub fn directory() -> PathBuf {
+    let directory = crate::settings::download_directory();
+    fs::create_dir_all(&directory).ok();
+    directory
+}
+
+// Picks a file name from the last path segment, adding an extension
+// matching the MIME type when the URL does not have one.
+pub fn file_name(url: &Url, mime: &str) -> String {
+    let segment = url
+        .path_segments()
+        .and_then(|segments| segments.filter(|s| !s.is_empty()).last())
+        .map(|s| sanitize(&percent_decode(s.as_bytes()).decode_utf8_lossy()))
+        .unwrap_or_default();
+
+    // a host name never carries a file extension
+    let (name, has_extension) = if segment.is_empty() {
+        (sanitize(url.host_str().unwrap_or("")), false)
+    } else {
+        let has_extension = Path::new(&segment).extension().is_some();
+        (segment, has_extension)
+    };
+    let name = if name.is_empty() {
+        String::from("download")
+    } else {
+        name
+    };
+
+    match extension(mime) {
+        Some(ext) if !has_extension => format!("{}.{}", name, ext),
+        _ => name,
+    }
+}
+
+// Adds a counter to the name until it does not clash with an existing file.
+pub fn unique_path(directory: &Path, name: &str) -> PathBuf {
+    let path = directory.join(name);
+    if !path.exists() {
+        return path;
+    }
+
+    let stem = Path::new(name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| name.to_string());
+    let ext = Path::new(name)
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut counter = 1;
+    loop {
+        let path = directory.join(format!("{} ({}){}", stem, counter, ext));
+        if !path.exists() {
+            return path;
+        }
+        counter += 1;
+    }
+}
+
+pub fn is_executable(name: &str, mime: &str) -> bool {
+    let by_extension = Path::new(name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .map_or(false, |e| EXECUTABLE_EXTENSIONS.contains(&e.as_str()));
+
+    by_extension || EXECUTABLE_TYPES.contains(&mime)
+}
+
+pub fn has_executable_header(path: &Path) -> bool {
+    let mut header = [0; 4];
+    match File::open(path).and_then(|mut file| file.read(&mut header)) {
+        Ok(size) => EXECUTABLE_HEADERS
+            .iter()
+            .any(|magic| header[..size].starts_with(magic)),
+        Err(_) => true,
+    }
+}
+
+// Writes what was already received followed by the rest of the stream,
+// reporting the total written so far after every chunk.
+pub fn save<F: FnMut(u64)>(
+    path: &Path,
+    received: &[u8],
+    mut stream: Stream,
+    mut progress: F,
+) -> Result<u64, String> {
+    let result = (|| {
+        let mut file = File::create(path).map_err(|e| e.to_string())?;
+        file.write_all(received).map_err(|e| e.to_string())?;
+
+        let mut total = received.len() as u64;
+        let mut buffer = vec![0; 16 * 1024];
+        progress(total);
+
+        loop {
+            match stream.read(&mut buffer) {
+                Ok(0) => return Ok(total),
+                Ok(size) => {
+                    file.write_all(&buffer[..size])
+                        .map_err(|e| e.to_string())?;
+                    total += size as u64;
+                    progress(total);
+                }
+                Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+    })();
+
+    if result.is_err() {
+        fs::remove_file(path).ok();
+    }
+    result
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim_start_matches(|c| c == '.' || c == ' ')
+        .trim_end()
+        .to_string()
+}
+
+fn extension(mime: &str) -> Option<&'static str> {
+    let mime = mime.split(';').next().unwrap_or("").trim();
+    match mime {
+        "text/gemini" => Some("gmi"),
+        "text/plain" => Some("txt"),
+        "text/html" => Some("html"),
+        "text/markdown" => Some("md"),
+        "text/csv" => Some("csv"),
+        "image/png" => Some("png"),
+        "image/jpeg" => Some("jpg"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        "image/svg+xml" => Some("svg"),
+        "audio/mpeg" => Some("mp3"),
+        "audio/ogg" => Some("ogg"),
+        "audio/flac" => Some("flac"),
+        "video/mp4" => Some("mp4"),
+        "video/webm" => Some("webm"),
+        "application/pdf" => Some("pdf"),
+        "application/zip" => Some("zip"),
+        "application/gzip" => Some("gz"),
+        "application/x-tar" => Some("tar"),
+        "application/epub+zip" => Some("epub"),
+        "application/json" => Some("json"),
+        "application/xml" | "application/atom+xml" | "application/rss+xml" => Some("xml"),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_file_name_from_path() {
+    let url = Url::parse("gemini://typed-hole.org/files/castor%20manual.pdf").unwrap();
+    assert_eq!(file_name(&url, "application/pdf"), "castor manual.pdf");
+}
+
+#[test]
+fn test_file_name_extension_from_mime() {
+    let url = Url::parse("gemini://typed-hole.org/images/logo").unwrap();
+    assert_eq!(file_name(&url, "image/png"), "logo.png");
+}
+
+#[test]
+fn test_file_name_without_path() {
+    let url = Url::parse("gemini://typed-hole.org/").unwrap();
+    assert_eq!(file_name(&url, "text/gemini; lang=en"), "typed-hole.org.gmi");
+}
+
+#[test]
+fn test_file_name_is_sanitized() {
+    let url = Url::parse("gopher://typed-hole.org/9/..%2F..%2Fbashrc").unwrap();
+    assert_eq!(file_name(&url, ""), "_.._bashrc");
+}
+
+#[test]
+fn test_is_executable() {
+    assert!(is_executable("setup.EXE", ""));
+    assert!(is_executable("install", "application/x-sh"));
+    assert!(!is_executable("castor.pdf", "application/pdf"));
+}
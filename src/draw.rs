@@ -1,8 +1,13 @@
+use gdk_pixbuf::{InterpType, PixbufAnimationExt, PixbufLoader, PixbufLoaderExt};
 use glib::clone;
 use gtk::prelude::*;
 use gtk::TextBuffer;
+use linkify::{LinkFinder, LinkKind};
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::thread;
 use url::Url;
 use std::convert::TryInto;
 
@@ -14,27 +19,40 @@ use crate::colors::*;
 use crate::gemini::link::Link as GeminiLink;
 use crate::gopher::link::Link as GopherLink;
 use crate::gui::Gui;
-use crate::protocols::{Finger, Gemini, Gopher};
+use crate::protocols::{File, Gemini, Gopher, Nex, Spartan};
 
+pub const IMAGE_TYPES: [&str; 4] = ["image/png", "image/jpeg", "image/gif", "image/webp"];
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "gif", "webp"];
 
 pub fn gemini_content(
     gui: &Arc<Gui>,
     content: Vec<Result<crate::gemini::parser::TextElement, crate::gemini::parser::ParseError>>,
+) -> TextBuffer {
+    let mut mono_toggle = false;
+    gemini_lines(gui, content, &mut mono_toggle, &mut colors::Ansi::new())
+}
+
+// Draws gemtext that may be a part of a larger document, `mono_toggle` keeps
+// track of preformatted blocks spanning several calls and `ansi` of colours.
+pub fn gemini_lines(
+    gui: &Arc<Gui>,
+    content: Vec<Result<crate::gemini::parser::TextElement, crate::gemini::parser::ParseError>>,
+    mono_toggle: &mut bool,
+    ansi: &mut colors::Ansi,
 ) -> TextBuffer {
     let content_view = gui.content_view();
     let buffer = content_view.get_buffer().unwrap();
 
-    let mut mono_toggle = false;
     let font_family = crate::settings::get_gemini_text_font_family();
 
     for el in content {
         match el {
             Ok(crate::gemini::parser::TextElement::MonoText(_text)) => {
-                mono_toggle = !mono_toggle;
+                *mono_toggle = !*mono_toggle;
             }
             Ok(crate::gemini::parser::TextElement::H1(header)) => {
                 let mut end_iter = buffer.get_end_iter();
-                if mono_toggle {
+                if *mono_toggle {
                     buffer.insert_markup(&mut end_iter, &mono_span(escape_text(&header)));
                 } else {
                   buffer.insert_markup(
@@ -53,7 +71,7 @@ pub fn gemini_content(
             }
             Ok(crate::gemini::parser::TextElement::H2(header)) => {
                 let mut end_iter = buffer.get_end_iter();
-                if mono_toggle {
+                if *mono_toggle {
                     buffer.insert_markup(&mut end_iter, &mono_span(escape_text(&header)));
                } else {
                   buffer.insert_markup(
@@ -72,7 +90,7 @@ pub fn gemini_content(
             }
             Ok(crate::gemini::parser::TextElement::H3(header)) => {
                 let mut end_iter = buffer.get_end_iter();
-                if mono_toggle {
+                if *mono_toggle {
                     buffer.insert_markup(&mut end_iter, &mono_span(escape_text(&header)));
                 } else {
                     buffer.insert_markup(
@@ -91,7 +109,7 @@ pub fn gemini_content(
             }
             Ok(crate::gemini::parser::TextElement::ListItem(item)) => {
                 let mut end_iter = buffer.get_end_iter();
-                if mono_toggle {
+                if *mono_toggle {
                     buffer.insert_markup(&mut end_iter, &mono_span(item));
                 } else {
                     buffer.insert_markup(
@@ -110,7 +128,7 @@ pub fn gemini_content(
             }
             Ok(crate::gemini::parser::TextElement::Quote(text)) => {
                 let mut end_iter = buffer.get_end_iter();
-                if mono_toggle {
+                if *mono_toggle {
                     buffer.insert_markup(&mut end_iter, &mono_span(text));
                 } else {
                     buffer.insert_markup(
@@ -129,8 +147,8 @@ pub fn gemini_content(
             }
             Ok(crate::gemini::parser::TextElement::Text(text)) => {
                 let mut end_iter = buffer.get_end_iter();
-                if mono_toggle {
-                    buffer.insert_markup(&mut end_iter, &mono_span(colors::colorize(&text)));
+                if *mono_toggle {
+                    buffer.insert_markup(&mut end_iter, &mono_span(ansi.markup(&text)));
                 } else {
                     buffer.insert_markup(
                         &mut end_iter,
@@ -145,20 +163,197 @@ pub fn gemini_content(
                 }
             }
             Ok(crate::gemini::parser::TextElement::LinkItem(link_item)) => {
-                if mono_toggle {
+                if *mono_toggle {
                     let mut end_iter = buffer.get_end_iter();
                     buffer.insert_markup(&mut end_iter, &mono_span(escape_text(&link_item)));
                 } else {
                     gemini_link(&gui, link_item);
                 }
             }
+            Ok(crate::gemini::parser::TextElement::PromptItem(prompt_item)) => {
+                if *mono_toggle {
+                    let mut end_iter = buffer.get_end_iter();
+                    buffer.insert_markup(&mut end_iter, &mono_span(escape_text(&prompt_item)));
+                } else {
+                    gemini_prompt(&gui, prompt_item);
+                }
+            }
             Err(_) => println!("Something failed."),
         }
     }
     buffer
 }
 
-pub fn gemini_text_content(gui: &Arc<Gui>, content: std::str::Lines) -> TextBuffer {
+// Markdown is drawn with the gemtext styles, its inline links can be clicked.
+pub fn markdown_lines(
+    gui: &Arc<Gui>,
+    content: Vec<crate::markdown::parser::TextElement>,
+) -> TextBuffer {
+    use crate::markdown::parser::TextElement;
+
+    let content_view = gui.content_view();
+    let buffer = content_view.get_buffer().unwrap();
+    let heading = |color: String, size: i32, family: String, style: String| {
+        format!(
+            "<span foreground=\"{}\" size=\"{}\" font_family=\"{}\" style=\"{}\">",
+            color, size, family, style
+        )
+    };
+
+    for el in content {
+        let (open, prefix, wrap, spans) = match el {
+            TextElement::H1(spans) => (
+                heading(
+                    crate::settings::get_h1_color(),
+                    crate::settings::get_gemini_h1_font_size(),
+                    crate::settings::get_gemini_h1_font_family(),
+                    crate::settings::get_gemini_h1_font_style(),
+                ),
+                crate::settings::get_h1_character(),
+                false,
+                spans,
+            ),
+            TextElement::H2(spans) => (
+                heading(
+                    crate::settings::get_h2_color(),
+                    crate::settings::get_gemini_h2_font_size(),
+                    crate::settings::get_gemini_h2_font_family(),
+                    crate::settings::get_gemini_h2_font_style(),
+                ),
+                crate::settings::get_h2_character(),
+                false,
+                spans,
+            ),
+            TextElement::H3(spans) => (
+                heading(
+                    crate::settings::get_h3_color(),
+                    crate::settings::get_gemini_h3_font_size(),
+                    crate::settings::get_gemini_h3_font_family(),
+                    crate::settings::get_gemini_h3_font_style(),
+                ),
+                crate::settings::get_h3_character(),
+                false,
+                spans,
+            ),
+            TextElement::ListItem(spans) => (
+                heading(
+                    crate::settings::get_list_color(),
+                    crate::settings::get_gemini_list_font_size(),
+                    crate::settings::get_gemini_list_font_family(),
+                    crate::settings::get_gemini_list_font_style(),
+                ),
+                crate::settings::get_list_character(),
+                true,
+                spans,
+            ),
+            TextElement::Quote(spans) => (
+                format!(
+                    "<span foreground=\"{}\" background=\"{}\" font_family=\"{}\" size=\"{}\" style=\"{}\">",
+                    crate::settings::get_gemini_quote_foreground_color(),
+                    crate::settings::get_gemini_quote_background_color(),
+                    crate::settings::get_gemini_quote_font_family(),
+                    crate::settings::get_gemini_quote_font_size(),
+                    crate::settings::get_gemini_quote_font_style(),
+                ),
+                String::new(),
+                true,
+                spans,
+            ),
+            TextElement::Text(spans) => (
+                format!(
+                    "<span foreground=\"{}\" font_family=\"{}\" size=\"{}\">",
+                    crate::settings::get_text_color(),
+                    crate::settings::get_gemini_text_font_family(),
+                    crate::settings::get_gemini_text_font_size(),
+                ),
+                String::new(),
+                true,
+                spans,
+            ),
+            TextElement::Code(line) => {
+                let mut end_iter = buffer.get_end_iter();
+                buffer.insert_markup(&mut end_iter, &mono_span(escape_text(&line)));
+                continue;
+            }
+            TextElement::Rule => {
+                let mut end_iter = buffer.get_end_iter();
+                let rule = "─".repeat(std::cmp::min(width(gui), 40));
+                buffer.insert_markup(&mut end_iter, &mono_span(rule));
+                continue;
+            }
+        };
+        markdown_line(gui, &open, &prefix, &spans, wrap);
+    }
+    buffer
+}
+
+// Draws spans inside the `open` span tag, on as many lines as needed when
+// `wrap` is set. Relative links are resolved against the current page.
+fn markdown_line(
+    gui: &Arc<Gui>,
+    open: &str,
+    prefix: &str,
+    spans: &[crate::markdown::parser::Span],
+    wrap: bool,
+) {
+    use crate::markdown::parser::{self, Span};
+
+    let content_view = gui.content_view();
+    let buffer = content_view.get_buffer().unwrap();
+    let width = if wrap { width(gui) } else { usize::MAX };
+    let base = crate::history::get_current_url();
+    let page_scheme = base.as_ref().map(|base| base.scheme().to_string());
+
+    if !prefix.is_empty() {
+        let mut end_iter = buffer.get_end_iter();
+        buffer.insert_markup(
+            &mut end_iter,
+            &format!("{}{}</span>", open, escape_text(prefix)),
+        );
+    }
+
+    for line in parser::wrap(spans, width) {
+        for span in line {
+            let link = match &span {
+                Span::Link(_, target) => match &base {
+                    Some(base) => base.join(target).ok(),
+                    None => Url::parse(target).ok(),
+                }
+                .and_then(|url| Some((text_link_action(&url, page_scheme.as_deref())?, url))),
+                _ => None,
+            };
+            let markup = match &span {
+                Span::Text(text) => escape_text(text),
+                Span::Emphasis(text) => format!("<i>{}</i>", escape_text(text)),
+                Span::Strong(text) => format!("<b>{}</b>", escape_text(text)),
+                Span::Code(text) => format!("<tt>{}</tt>", escape_text(text)),
+                Span::Link(label, _) if link.is_some() => {
+                    format!("<u>{}</u>", escape_text(label))
+                }
+                Span::Link(label, _) => escape_text(label),
+            };
+            let markup = format!("{}{}</span>", open, markup);
+
+            match link {
+                Some((action, url)) => {
+                    insert_text_link(gui, &markup, url, action, String::new());
+                }
+                None => {
+                    let mut end_iter = buffer.get_end_iter();
+                    buffer.insert_markup(&mut end_iter, &markup);
+                }
+            }
+        }
+        let mut end_iter = buffer.get_end_iter();
+        buffer.insert(&mut end_iter, "\n");
+    }
+}
+
+pub fn gemini_text_content(
+    gui: &Arc<Gui>,
+    content: std::str::Lines,
+    ansi: &mut colors::Ansi,
+) -> TextBuffer {
     let content_view = gui.content_view();
     let buffer = content_view.get_buffer().unwrap();
 
@@ -169,16 +364,44 @@ pub fn gemini_text_content(gui: &Arc<Gui>, content: std::str::Lines) -> TextBuff
             &format!(
                 "<span foreground=\"{}\" font_family=\"monospace\">{}</span>\n",
                 crate::settings::get_text_color(),
-                colors::colorize(&line)
+                ansi.markup(&line)
             ),
         );
     }
     buffer
 }
 
+// Nex directories are plain text with gemtext link lines.
+pub fn nex_content(
+    gui: &Arc<Gui>,
+    content: std::str::Lines,
+    ansi: &mut colors::Ansi,
+) -> TextBuffer {
+    let content_view = gui.content_view();
+    let buffer = content_view.get_buffer().unwrap();
+
+    for line in content {
+        if line.starts_with("=>") {
+            gemini_link(&gui, line.to_string());
+        } else {
+            let mut end_iter = buffer.get_end_iter();
+            buffer.insert_markup(
+                &mut end_iter,
+                &format!(
+                    "<span foreground=\"{}\" font_family=\"monospace\">{}</span>\n",
+                    crate::settings::get_text_color(),
+                    ansi.markup(&line)
+                ),
+            );
+        }
+    }
+    buffer
+}
+
 pub fn gopher_content(
     gui: &Arc<Gui>,
     content: Vec<Result<crate::gopher::parser::TextElement, crate::gopher::parser::ParseError>>,
+    ansi: &mut colors::Ansi,
 ) -> TextBuffer {
     let content_view = gui.content_view();
     let buffer = content_view.get_buffer().unwrap();
@@ -187,7 +410,7 @@ pub fn gopher_content(
         match el {
             Ok(crate::gopher::parser::TextElement::Text(text)) => {
                 let mut end_iter = buffer.get_end_iter();
-                let text = colors::colorize(&text);
+                let text = ansi.markup(&text);
 
                 buffer.insert_markup(
                     &mut end_iter,
@@ -200,6 +423,20 @@ pub fn gopher_content(
                     ),
                 );
             }
+            Ok(crate::gopher::parser::TextElement::Error(text)) => {
+                let mut end_iter = buffer.get_end_iter();
+
+                buffer.insert_markup(
+                    &mut end_iter,
+                    &format!(
+                        "<span foreground=\"{}\" font_family=\"{}\" size=\"{}\">⚠ {}</span>\n",
+                        crate::settings::get_text_color(),
+                        crate::settings::get_gopher_font_family(),
+                        crate::settings::get_gopher_font_size(),
+                        escape_text(&text)
+                    ),
+                );
+            }
             Ok(crate::gopher::parser::TextElement::LinkItem(link_item)) => {
                 gopher_link(&gui, colors::cleanup(&link_item));
             }
@@ -212,34 +449,197 @@ pub fn gopher_content(
             Ok(crate::gopher::parser::TextElement::Binary(link_item)) => {
                 gopher_link(&gui, link_item);
             }
+            Ok(crate::gopher::parser::TextElement::Search(link_item)) => {
+                gopher_link(&gui, colors::cleanup(&link_item));
+            }
             Err(_) => println!("Something failed."),
         }
     }
     buffer
 }
 
+// Lays a menu out as monospace text, with links as styled text instead of
+// buttons, so columns and ASCII art line up like the author intended.
+pub fn gopher_grid_content(
+    gui: &Arc<Gui>,
+    content: Vec<Result<crate::gopher::parser::TextElement, crate::gopher::parser::ParseError>>,
+    ansi: &mut colors::Ansi,
+) -> TextBuffer {
+    let content_view = gui.content_view();
+    let buffer = content_view.get_buffer().unwrap();
+
+    for el in content {
+        match el {
+            Ok(crate::gopher::parser::TextElement::Text(text)) => {
+                let mut end_iter = buffer.get_end_iter();
+                let text = grid_span(&ansi.markup(&text), false);
+                buffer.insert_markup(&mut end_iter, &format!("{}\n", text));
+            }
+            Ok(crate::gopher::parser::TextElement::Error(text)) => {
+                let mut end_iter = buffer.get_end_iter();
+                let text = grid_span(&format!("⚠ {}", escape_text(&text)), false);
+                buffer.insert_markup(&mut end_iter, &format!("{}\n", text));
+            }
+            Ok(crate::gopher::parser::TextElement::LinkItem(link_item))
+            | Ok(crate::gopher::parser::TextElement::ExternalLinkItem(link_item))
+            | Ok(crate::gopher::parser::TextElement::Image(link_item))
+            | Ok(crate::gopher::parser::TextElement::Binary(link_item))
+            | Ok(crate::gopher::parser::TextElement::Search(link_item)) => {
+                gopher_grid_link(gui, link_item);
+            }
+            Err(_) => (),
+        }
+    }
+    buffer
+}
+
+#[derive(Clone, Copy)]
+enum LinkAction {
+    Visit,
+    Download,
+    Search,
+    External,
+}
+
+fn gopher_grid_link(gui: &Arc<Gui>, link_item: String) {
+    let (url, label, action) = match GopherLink::from_str(&link_item) {
+        Ok(GopherLink::Gopher(url, label)) => (keep_tls(url), label, LinkAction::Visit),
+        Ok(GopherLink::Gemini(url, label)) | Ok(GopherLink::Finger(url, label)) => {
+            (url, label, LinkAction::Visit)
+        }
+        Ok(GopherLink::Relative(url, label)) => match (Gopher { source: url }).to_absolute_url() {
+            Ok(url) => (url, label, LinkAction::Visit),
+            Err(_) => return,
+        },
+        Ok(GopherLink::Image(url, label)) | Ok(GopherLink::File(url, label, _)) => {
+            (keep_tls(url), label, LinkAction::Download)
+        }
+        Ok(GopherLink::Search(url, label)) => (keep_tls(url), label, LinkAction::Search),
+        Ok(GopherLink::Http(url, label))
+        | Ok(GopherLink::Telnet(url, label))
+        | Ok(GopherLink::Ftp(url, label)) => (url, label, LinkAction::External),
+        Ok(GopherLink::Unknown(_, _)) | Err(_) => return,
+    };
+
+    let content_view = gui.content_view();
+    let buffer = content_view.get_buffer().unwrap();
+    let message = label.clone();
+    let label = if label.is_empty() {
+        escape_text(url.as_str())
+    } else {
+        colors::cleanup(&label)
+    };
+
+    insert_text_link(gui, &grid_span(&label, true), url, action, message);
+    let mut end_iter = buffer.get_end_iter();
+    buffer.insert(&mut end_iter, "\n");
+}
+
+// Inserts styled text that acts like a link button when clicked.
+fn insert_text_link(gui: &Arc<Gui>, markup: &str, url: Url, action: LinkAction, message: String) {
+    let content_view = gui.content_view();
+    let buffer = content_view.get_buffer().unwrap();
+
+    let start = buffer.get_end_iter().get_offset();
+    let mut end_iter = buffer.get_end_iter();
+    buffer.insert_markup(&mut end_iter, markup);
+
+    let tag = gtk::TextTag::new(None);
+    buffer.get_tag_table().unwrap().add(&tag);
+    buffer.apply_tag(
+        &tag,
+        &buffer.get_iter_at_offset(start),
+        &buffer.get_end_iter(),
+    );
+
+    tag.connect_event(
+        clone!(@weak gui => @default-return Inhibit(false), move |_, _, event, _| {
+            let released = event.get_event_type() == gdk::EventType::ButtonRelease;
+            if !released || event.get_button() != Some(1) {
+                return Inhibit(false);
+            }
+
+            // the page is replaced once the event is handled
+            let gui = gui.clone();
+            let url = url.clone();
+            let message = message.clone();
+            glib::idle_add_local(move || {
+                match action {
+                    LinkAction::Visit => crate::visit(&gui, &url),
+                    LinkAction::Download => download_gopher_file(&gui, &url),
+                    LinkAction::Search => crate::dialog::input(&gui, url.clone(), &message),
+                    LinkAction::External => open_external(&gui, &url),
+                }
+                glib::Continue(false)
+            });
+            Inhibit(true)
+        }),
+    );
+}
+
+// Links found in text are visited when Castor can, opened outside otherwise.
+// Local files are only linked from local pages, None leaves the link as text.
+fn text_link_action(url: &Url, page_scheme: Option<&str>) -> Option<LinkAction> {
+    match url.scheme() {
+        "file" if page_scheme == Some("file") => Some(LinkAction::Visit),
+        "file" => None,
+        "about" | "finger" | "gemini" | "gopher" | "gophers" | "nex" | "spartan" => {
+            Some(LinkAction::Visit)
+        }
+        _ => Some(LinkAction::External),
+    }
+}
+
+fn grid_span(text: &str, link: bool) -> String {
+    format!(
+        "<span foreground=\"{}\" font_family=\"monospace\" size=\"{}\"{}>{}</span>",
+        crate::settings::get_text_color(),
+        crate::settings::get_gopher_font_size(),
+        if link { " underline=\"single\"" } else { "" },
+        text
+    )
+}
+
 pub fn finger_content(
     gui: &Arc<Gui>,
     content: Vec<Result<crate::finger::parser::TextElement, crate::finger::parser::ParseError>>,
 ) -> TextBuffer {
     let content_view = gui.content_view();
     let buffer = content_view.get_buffer().unwrap();
+    let page_scheme = crate::history::get_current_scheme();
 
     for el in content {
         match el {
             Ok(crate::finger::parser::TextElement::Text(text)) => {
                 let mut end_iter = buffer.get_end_iter();
+                let text = finger_span(&escape_text(&text), false);
+                buffer.insert_markup(&mut end_iter, &format!("{}\n", text));
+            }
+            Ok(crate::finger::parser::TextElement::Linked(text)) => {
+                let mut finder = LinkFinder::new();
+                finder.kinds(&[LinkKind::Url]);
 
-                buffer.insert_markup(
-                    &mut end_iter,
-                    &format!(
-                        "<span foreground=\"{}\" font_family=\"{}\" size=\"{}\">{}</span>\n",
-                        crate::settings::get_text_color(),
-                        crate::settings::get_finger_font_family(),
-                        crate::settings::get_finger_font_size(),
-                        escape_text(&text)
-                    ),
-                );
+                for span in finder.spans(&text) {
+                    let link = span
+                        .kind()
+                        .and_then(|_| Url::parse(span.as_str()).ok())
+                        .and_then(|url| {
+                            Some((text_link_action(&url, page_scheme.as_deref())?, url))
+                        });
+                    match link {
+                        Some((action, url)) => {
+                            let markup = finger_span(&escape_text(span.as_str()), true);
+                            insert_text_link(gui, &markup, url, action, String::new());
+                        }
+                        None => {
+                            let mut end_iter = buffer.get_end_iter();
+                            let markup = finger_span(&escape_text(span.as_str()), false);
+                            buffer.insert_markup(&mut end_iter, &markup);
+                        }
+                    }
+                }
+                let mut end_iter = buffer.get_end_iter();
+                buffer.insert(&mut end_iter, "\n");
             }
             Err(_) => println!("Something failed."),
         }
@@ -247,8 +647,29 @@ pub fn finger_content(
     buffer
 }
 
+fn finger_span(text: &str, link: bool) -> String {
+    format!(
+        "<span foreground=\"{}\" font_family=\"{}\" size=\"{}\"{}>{}</span>",
+        crate::settings::get_text_color(),
+        crate::settings::get_finger_font_family(),
+        crate::settings::get_finger_font_size(),
+        if link { " underline=\"single\"" } else { "" },
+        text
+    )
+}
+
 pub fn gemini_link(gui: &Arc<Gui>, link_item: String) {
     match GeminiLink::from_str(&link_item) {
+        Ok(GeminiLink::File(url, label)) => {
+            // only local pages can link to local files
+            if crate::history::get_current_scheme().as_deref() != Some("file") {
+                return;
+            }
+            insert_button(&gui, url.clone(), label);
+            if is_image_url(&url) {
+                insert_image_toggle(&gui, url);
+            }
+        }
         Ok(GeminiLink::Finger(url, label)) => {
             let button_label = if label.is_empty() {
                 url.to_string()
@@ -259,7 +680,10 @@ pub fn gemini_link(gui: &Arc<Gui>, link_item: String) {
             insert_button(&gui, url, finger_label);
         }
         Ok(GeminiLink::Gemini(url, label)) => {
-            insert_button(&gui, url, label);
+            insert_button(&gui, url.clone(), label);
+            if is_image_url(&url) {
+                insert_image_toggle(&gui, url);
+            }
         }
         Ok(GeminiLink::Gopher(url, label)) => {
             let button_label = if label.is_empty() {
@@ -270,6 +694,27 @@ pub fn gemini_link(gui: &Arc<Gui>, link_item: String) {
             let gopher_label = format!("{} [Gopher]", button_label);
             insert_button(&gui, url, gopher_label);
         }
+        Ok(GeminiLink::About(url, label)) => {
+            insert_button(&gui, url, label);
+        }
+        Ok(GeminiLink::Nex(url, label)) => {
+            let button_label = if label.is_empty() {
+                url.to_string()
+            } else {
+                label
+            };
+            let nex_label = format!("{} [Nex]", button_label);
+            insert_button(&gui, url, nex_label);
+        }
+        Ok(GeminiLink::Spartan(url, label)) => {
+            let button_label = if label.is_empty() {
+                url.to_string()
+            } else {
+                label
+            };
+            let spartan_label = format!("{} [Spartan]", button_label);
+            insert_button(&gui, url, spartan_label);
+        }
         Ok(GeminiLink::Http(url, label)) => {
             let button_label = if label.is_empty() {
                 url.to_string()
@@ -311,15 +756,63 @@ pub fn gemini_link(gui: &Arc<Gui>, link_item: String) {
             insert_external_button(&gui, url, &irc_label);
         }
         Ok(GeminiLink::Relative(url, label)) => {
-            let new_url = Gemini { source: url }.to_absolute_url().unwrap();
-            insert_button(&gui, new_url, label);
+            let new_url = match crate::history::get_current_scheme().as_deref() {
+                Some("file") => File { source: url }.to_absolute_url(),
+                Some("nex") => Nex { source: url }.to_absolute_url(),
+                Some("spartan") => Spartan { source: url }.to_absolute_url(),
+                _ => Gemini { source: url }.to_absolute_url(),
+            }
+            .unwrap();
+            insert_button(&gui, new_url.clone(), label);
+            if is_image_url(&new_url) {
+                insert_image_toggle(&gui, new_url);
+            }
         }
         Ok(GeminiLink::Unknown(_, _)) => (),
         Err(_) => (),
     }
 }
 
+// Spartan "=:" lines are links asking for some text to send along.
+pub fn gemini_prompt(gui: &Arc<Gui>, prompt_item: String) {
+    let link_item = prompt_item.replacen("=:", "=>", 1);
+    let (url, label) = match GeminiLink::from_str(&link_item) {
+        Ok(GeminiLink::Spartan(url, label)) => (url, label),
+        Ok(GeminiLink::Relative(url, label)) => {
+            let spartan = Spartan { source: url };
+            match spartan.to_absolute_url() {
+                Ok(url) => (url, label),
+                Err(_) => return,
+            }
+        }
+        _ => return,
+    };
+
+    insert_input_button(&gui, url, format!("{} [Input]", label), label);
+}
+
+// Asks for some text with `message` before following the link.
+pub fn insert_input_button(gui: &Arc<Gui>, url: Url, label: String, message: String) {
+    let content_view = gui.content_view();
+    let buffer = content_view.get_buffer().unwrap();
+
+    let button = gtk::Button::new_with_label(&label);
+    button.set_tooltip_text(Some(&url.to_string()));
+
+    button.connect_clicked(clone!(@weak gui => move |_| {
+        crate::dialog::input(&gui, url.clone(), &message);
+    }));
+
+    let mut start_iter = buffer.get_end_iter();
+    let anchor = buffer.create_child_anchor(&mut start_iter).unwrap();
+    content_view.add_child_at_anchor(&button, &anchor);
+    let mut end_iter = buffer.get_end_iter();
+    buffer.insert(&mut end_iter, "\n");
+}
+
 pub fn gopher_link(gui: &Arc<Gui>, link_item: String) {
+    let gopher_plus = crate::gopher::plus::is_gopher_plus(&link_item);
+
     match GopherLink::from_str(&link_item) {
         Ok(GopherLink::Http(url, label)) => {
             let button_label = if label.is_empty() {
@@ -338,7 +831,11 @@ pub fn gopher_link(gui: &Arc<Gui>, link_item: String) {
                 label
             };
             let gopher_label = format!("{} [Gopher]", button_label);
-            insert_button(&gui, url, gopher_label);
+            let url = keep_tls(url);
+            insert_button(&gui, url.clone(), gopher_label);
+            if gopher_plus {
+                insert_info_button(&gui, url);
+            }
         }
         Ok(GopherLink::Image(url, label)) => {
             let button_label = if label.is_empty() {
@@ -347,16 +844,41 @@ pub fn gopher_link(gui: &Arc<Gui>, link_item: String) {
                 label
             };
             let image_label = format!("{} [Image]", button_label);
-            insert_gopher_file_button(&gui, url, image_label);
+            let url = keep_tls(url);
+            insert_gopher_file_button(&gui, url.clone(), image_label);
+            insert_image_toggle(&gui, url);
+        }
+        Ok(GopherLink::Search(url, label)) => {
+            let button_label = if label.is_empty() {
+                url.to_string()
+            } else {
+                label
+            };
+            let search_label = format!("{} [Search]", button_label);
+            insert_input_button(&gui, keep_tls(url), search_label, button_label);
         }
-        Ok(GopherLink::File(url, label)) => {
+        Ok(GopherLink::File(url, label, kind)) => {
             let button_label = if label.is_empty() {
                 url.to_string()
             } else {
                 label
             };
-            let file_label = format!("{} [File]", button_label);
-            insert_gopher_file_button(&gui, url, file_label);
+            let file_label = format!("{} [{}]", button_label, kind);
+            let url = keep_tls(url);
+            insert_gopher_file_button(&gui, url.clone(), file_label);
+            if gopher_plus {
+                insert_info_button(&gui, url);
+            }
+        }
+        Ok(GopherLink::Telnet(url, label)) => {
+            let button_label = if label.is_empty() {
+                url.to_string()
+            } else {
+                label
+            };
+            let telnet_label = format!("{} [Telnet]", button_label);
+
+            insert_external_button(&gui, url, &telnet_label);
         }
         Ok(GopherLink::Gemini(url, label)) => {
             insert_button(&gui, url, label);
@@ -390,6 +912,37 @@ pub fn gopher_link(gui: &Arc<Gui>, link_item: String) {
     }
 }
 
+// Gopher+ items get a button listing their attributes.
+pub fn insert_info_button(gui: &Arc<Gui>, url: Url) {
+    let content_view = gui.content_view();
+    let buffer = content_view.get_buffer().unwrap();
+
+    let button = gtk::Button::new_with_label("Info");
+    button.set_tooltip_text(Some("Show the Gopher+ attributes of this item"));
+
+    // right before the newline ending the link line
+    let mut link_end = buffer.get_end_iter();
+    link_end.backward_char();
+    let anchor = buffer.create_child_anchor(&mut link_end).unwrap();
+    content_view.add_child_at_anchor(&button, &anchor);
+
+    button.connect_clicked(clone!(@weak gui => move |_| {
+        crate::show_item_info(&gui, &url);
+    }));
+}
+
+// Menus list items with gopher:// URLs, the ones on the server of a
+// gophers:// page are kept on TLS.
+fn keep_tls(mut url: Url) -> Url {
+    if crate::history::get_current_scheme().as_deref() == Some("gophers")
+        && url.scheme() == "gopher"
+        && url.host_str().map(String::from) == crate::history::get_current_host()
+    {
+        url.set_scheme("gophers").ok();
+    }
+    url
+}
+
 pub fn insert_button(gui: &Arc<Gui>, url: Url, label: String) {
     let content_view = gui.content_view();
     let buffer = content_view.get_buffer().unwrap();
@@ -404,12 +957,7 @@ pub fn insert_button(gui: &Arc<Gui>, url: Url, label: String) {
     button.set_tooltip_text(Some(&url.to_string()));
 
     button.connect_clicked(clone!(@weak gui => move |_| {
-        match url.scheme() {
-            "finger" => crate::visit_url(&gui, Finger { source: url.to_string() }),
-            "gemini" => crate::visit_url(&gui, Gemini { source: url.to_string() }),
-            "gopher" => crate::visit_url(&gui, Gopher { source: url.to_string() }),
-            _ => ()
-        }
+        crate::visit(&gui, &url);
     }));
 
     let mut start_iter = buffer.get_end_iter();
@@ -432,13 +980,9 @@ pub fn insert_gopher_file_button(gui: &Arc<Gui>, url: Url, label: String) {
     let button = gtk::Button::new_with_label(&button_label);
     button.set_tooltip_text(Some(&url.to_string()));
 
-    button.connect_clicked(move |_| {
-        let (_meta, content) = crate::gopher::client::get_data(Gopher {
-            source: url.to_string(),
-        })
-        .unwrap();
-        crate::client::download(content);
-    });
+    button.connect_clicked(clone!(@weak gui => move |_| {
+        download_gopher_file(&gui, &url);
+    }));
 
     let mut start_iter = buffer.get_end_iter();
     let anchor = buffer.create_child_anchor(&mut start_iter).unwrap();
@@ -454,9 +998,9 @@ pub fn insert_external_button(gui: &Arc<Gui>, url: Url, label: &str) {
     let button = gtk::Button::new_with_label(&label);
     button.set_tooltip_text(Some(&url.to_string()));
 
-    button.connect_clicked(move |_| {
-        open::that(url.to_string()).unwrap();
-    });
+    button.connect_clicked(clone!(@weak gui => move |_| {
+        open_external(&gui, &url);
+    }));
 
     let mut start_iter = buffer.get_end_iter();
     let anchor = buffer.create_child_anchor(&mut start_iter).unwrap();
@@ -465,6 +1009,232 @@ pub fn insert_external_button(gui: &Arc<Gui>, url: Url, label: &str) {
     buffer.insert(&mut end_iter, "\n");
 }
 
+// HTML pages are shown as text, this opens the original in the system browser.
+pub fn insert_browser_button(gui: &Arc<Gui>, url: &Url, html: &[u8]) {
+    let content_view = gui.content_view();
+    let buffer = content_view.get_buffer().unwrap();
+
+    let button = gtk::Button::new_with_label("Open in browser");
+    button.set_tooltip_text(Some(url.as_str()));
+
+    let url = url.clone();
+    let html = html.to_vec();
+    button.connect_clicked(clone!(@weak gui => move |_| {
+        if let Err(e) = open_in_browser(&url, &html) {
+            crate::dialog::error(&gui, &format!("\n{}\n", e));
+        }
+    }));
+
+    let mut start_iter = buffer.get_end_iter();
+    let anchor = buffer.create_child_anchor(&mut start_iter).unwrap();
+    content_view.add_child_at_anchor(&button, &anchor);
+    let mut end_iter = buffer.get_end_iter();
+    buffer.insert(&mut end_iter, "\n\n");
+}
+
+// Browsers can't fetch Gemini or Gopher pages, they get a copy instead. It
+// goes in the user's own cache, where other users can't replace it.
+fn open_in_browser(url: &Url, html: &[u8]) -> Result<(), String> {
+    let directory = dirs::cache_dir()
+        .ok_or_else(|| String::from("No cache directory to copy the page to"))?
+        .join("castor");
+    std::fs::create_dir_all(&directory).map_err(|e| e.to_string())?;
+    let mut name = crate::downloads::file_name(url, "text/html");
+    if !name.ends_with(".html") && !name.ends_with(".htm") {
+        name.push_str(".html");
+    }
+    let path = directory.join(name);
+    std::fs::write(&path, browser_copy(url, html)).map_err(|e| e.to_string())?;
+    match open::that(&path) {
+        Ok(status) if status.success() => Ok(()),
+        Ok(_) => Err(format!("No application could open {}", path.display())),
+        Err(e) => Err(format!("Could not open {}\n{}", path.display(), e)),
+    }
+}
+
+// Relative links of the copy point back to the original page, and its
+// scripts don't run, they would be able to read the files next to it.
+fn browser_copy(url: &Url, html: &[u8]) -> Vec<u8> {
+    let head = format!(
+        "<meta http-equiv=\"Content-Security-Policy\" content=\"script-src 'none'\">\
+         <base href=\"{}\">",
+        url.as_str().replace('&', "&amp;").replace('"', "&quot;")
+    );
+    // before the doctype, the page would be drawn in quirks mode
+    let start = html
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(0);
+    let doctype = html[start..]
+        .get(..9)
+        .is_some_and(|tag| tag.eq_ignore_ascii_case(b"<!doctype"));
+    let at = match html[start..].iter().position(|b| *b == b'>') {
+        Some(end) if doctype => start + end + 1,
+        _ => 0,
+    };
+
+    let mut copy = html[..at].to_vec();
+    copy.extend_from_slice(head.as_bytes());
+    copy.extend_from_slice(&html[at..]);
+    copy
+}
+
+fn download_gopher_file(gui: &Arc<Gui>, url: &Url) {
+    match crate::gopher::client::get_stream(Gopher {
+        source: url.to_string(),
+    }) {
+        Ok((_meta, body)) => crate::download(gui, url, "", body),
+        Err(e) => crate::dialog::error(gui, &format!("\n{}\n", e)),
+    }
+}
+
+fn open_external(gui: &Arc<Gui>, url: &Url) {
+    let message = match open::that(url.to_string()) {
+        Ok(status) if status.success() => return,
+        Ok(_) => format!("\nNo application could open {}\n", url),
+        Err(e) => format!("\nCould not open {}\n{}\n", url, e),
+    };
+    crate::dialog::error(gui, &message);
+}
+
+// Draws a whole image, for responses that are just an image.
+pub fn image(gui: &Arc<Gui>, data: &[u8]) {
+    let content_view = gui.content_view();
+    let buffer = content_view.get_buffer().unwrap();
+    let mut end_iter = buffer.get_end_iter();
+
+    match load_image(&gui, data) {
+        Ok(image) => {
+            let anchor = buffer.create_child_anchor(&mut end_iter).unwrap();
+            content_view.add_child_at_anchor(&image, &anchor);
+            let mut end_iter = buffer.get_end_iter();
+            buffer.insert(&mut end_iter, "\n");
+        }
+        Err(e) => {
+            buffer.insert_markup(
+                &mut end_iter,
+                &format!(
+                    "<span foreground=\"{}\">Could not display image: {}</span>\n",
+                    crate::settings::get_text_color(),
+                    escape_text(&e)
+                ),
+            );
+        }
+    }
+}
+
+// Adds a toggle on the line of the link that was just inserted, showing the
+// image below the link once fetched.
+pub fn insert_image_toggle(gui: &Arc<Gui>, url: Url) {
+    if !crate::settings::inline_images() {
+        return;
+    }
+
+    let content_view = gui.content_view();
+    let buffer = content_view.get_buffer().unwrap();
+
+    let toggle = gtk::ToggleButton::new_with_label("Show image");
+    toggle.set_tooltip_text(Some(&url.to_string()));
+
+    // right before the newline ending the link line
+    let mut link_end = buffer.get_end_iter();
+    link_end.backward_char();
+    let anchor = buffer.create_child_anchor(&mut link_end).unwrap();
+    content_view.add_child_at_anchor(&toggle, &anchor);
+
+    let image_mark = buffer.create_mark(None, &buffer.get_end_iter(), true).unwrap();
+    let image: Rc<RefCell<Option<gtk::Image>>> = Rc::new(RefCell::new(None));
+
+    toggle.connect_toggled(clone!(@weak gui => move |toggle| {
+        if let Some(image) = image.borrow().as_ref() {
+            if toggle.get_active() {
+                image.show();
+            } else {
+                image.hide();
+            }
+            return;
+        }
+        if !toggle.get_active() {
+            return;
+        }
+
+        let page = crate::render::current_page();
+        let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+        let fetch_url = url.clone();
+        thread::spawn(move || {
+            let _ = sender.send(crate::client::fetch(&fetch_url, crate::settings::max_body_size()));
+        });
+
+        toggle.set_sensitive(false);
+        let image = image.clone();
+        let image_mark = image_mark.clone();
+        let toggle = toggle.clone();
+        receiver.attach(None, move |result| {
+            if crate::render::current_page() != page {
+                return glib::Continue(false);
+            }
+            toggle.set_sensitive(true);
+
+            match result.and_then(|(_mime, data)| load_image(&gui, &data)) {
+                Ok(new_image) => {
+                    let content_view = gui.content_view();
+                    let buffer = content_view.get_buffer().unwrap();
+                    let mut iter = buffer.get_iter_at_mark(&image_mark);
+                    let anchor = buffer.create_child_anchor(&mut iter).unwrap();
+                    content_view.add_child_at_anchor(&new_image, &anchor);
+                    buffer.insert(&mut iter, "\n");
+                    new_image.set_visible(toggle.get_active());
+                    image.replace(Some(new_image));
+                }
+                Err(e) => {
+                    toggle.set_active(false);
+                    crate::dialog::error(&gui, &format!("\n{}\n", e));
+                }
+            }
+            glib::Continue(false)
+        });
+    }));
+}
+
+pub fn is_image_url(url: &Url) -> bool {
+    let path = url.path().to_lowercase();
+    IMAGE_EXTENSIONS
+        .iter()
+        .any(|ext| path.ends_with(&format!(".{}", ext)))
+}
+
+// Decodes an image, scaling it down to fit the width of the content view.
+// Animations are kept as they are.
+fn load_image(gui: &Arc<Gui>, data: &[u8]) -> Result<gtk::Image, String> {
+    let loader = PixbufLoader::new();
+    loader
+        .write(data)
+        .and_then(|_| loader.close())
+        .map_err(|e| e.to_string())?;
+
+    if let Some(animation) = loader.get_animation() {
+        if !animation.is_static_image() {
+            return Ok(gtk::Image::new_from_animation(&animation));
+        }
+    }
+
+    let pixbuf = loader
+        .get_pixbuf()
+        .ok_or_else(|| String::from("Unknown image format"))?;
+
+    let max_width = gui.content_view().get_allocated_width() - 50;
+    let pixbuf = if max_width > 0 && pixbuf.get_width() > max_width {
+        let height = pixbuf.get_height() * max_width / pixbuf.get_width();
+        pixbuf
+            .scale_simple(max_width, std::cmp::max(height, 1), InterpType::Bilinear)
+            .unwrap_or(pixbuf)
+    } else {
+        pixbuf
+    };
+
+    Ok(gtk::Image::new_from_pixbuf(Some(&pixbuf)))
+}
+
 fn wrap_text(str: &str, gui: &Arc<Gui>) -> String {
     fill(&escape_text(str), width(&gui))
 }
@@ -487,3 +1257,42 @@ fn width(gui: &Arc<Gui>) -> usize {
     let calculated_width = (win_width / 10).try_into().unwrap();
     std::cmp::min(calculated_width, crate::settings::max_width().unwrap_or(std::usize::MAX))
 }
+
+#[test]
+fn test_text_link_action() {
+    let file = Url::parse("file:///home/me/notes/todo.md").unwrap();
+    assert!(matches!(
+        text_link_action(&file, Some("file")),
+        Some(LinkAction::Visit)
+    ));
+    assert!(text_link_action(&file, Some("gemini")).is_none());
+    assert!(text_link_action(&file, Some("finger")).is_none());
+    assert!(text_link_action(&file, None).is_none());
+
+    let about = Url::parse("about:help").unwrap();
+    assert!(matches!(
+        text_link_action(&about, Some("gemini")),
+        Some(LinkAction::Visit)
+    ));
+    let web = Url::parse("https://example.org/").unwrap();
+    assert!(matches!(
+        text_link_action(&web, Some("file")),
+        Some(LinkAction::External)
+    ));
+}
+
+#[test]
+fn test_browser_copy() {
+    let url = Url::parse("gemini://example.org/a?b&c").unwrap();
+    let head = "<meta http-equiv=\"Content-Security-Policy\" content=\"script-src 'none'\">\
+                <base href=\"gemini://example.org/a?b&amp;c\">";
+
+    assert_eq!(
+        String::from_utf8(browser_copy(&url, b"\n<!DOCTYPE html>\n<p>Hi</p>")).unwrap(),
+        format!("\n<!DOCTYPE html>{}\n<p>Hi</p>", head)
+    );
+    assert_eq!(
+        String::from_utf8(browser_copy(&url, b"<p>Hi</p>")).unwrap(),
+        format!("{}<p>Hi</p>", head)
+    );
+}
@@ -1,27 +0,0 @@
-use std::str::FromStr;
-
-#[derive(Debug)]
-pub enum TextElement {
-    Text(String),
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct ParseError;
-
-impl FromStr for TextElement {
-    type Err = ParseError;
-
-    // Parses a &str into an instance of 'TextElement'
-    fn from_str(line: &str) -> Result<TextElement, ParseError> {
-        Ok(TextElement::Text(line.to_string()))
-    }
-}
-
-pub fn parse(content: String) -> Vec<Result<TextElement, ParseError>> {
-    let mut parsed = Vec::new();
-
-    for line in content.lines() {
-        parsed.push(TextElement::from_str(line));
-    }
-    parsed
-}
@@ -1,5 +1,5 @@
 use gtk::prelude::*;
-use gtk::{Window, Button, Entry, TextView};
+use gtk::{Window, Button, Entry, Label, TextView};
 
 use gdk::WindowExt;
 
@@ -12,6 +12,8 @@ pub struct Gui {
     refresh_button: Button,
     add_bookmark_button: Button,
     show_bookmarks_button: Button,
+    edit_button: Button,
+    status_label: Label,
 }
 
 impl Default for Gui {
@@ -47,6 +49,12 @@ impl Gui {
         let show_bookmarks_button: Button = builder
             .get_object("show_bookmarks_button")
             .expect("Couldn't get show_bookmarks_button");
+        let edit_button: Button = builder
+            .get_object("edit_button")
+            .expect("Couldn't get edit_button");
+        let status_label: Label = builder
+            .get_object("status_label")
+            .expect("Couldn't get status_label");
 
         Gui {
             window,
@@ -57,6 +65,8 @@ impl Gui {
             refresh_button,
             add_bookmark_button,
             show_bookmarks_button,
+            edit_button,
+            status_label,
         }
     }
 
@@ -106,4 +116,12 @@ impl Gui {
     pub fn show_bookmarks_button(&self) -> &Button {
         &self.show_bookmarks_button
     }
+
+    pub fn edit_button(&self) -> &Button {
+        &self.edit_button
+    }
+
+    pub fn status_label(&self) -> &Label {
+        &self.status_label
+    }
 }
@@ -44,7 +44,15 @@ impl History {
     }
 
     fn append(&mut self, url: &str) {
-        let url = Url::parse(url).unwrap();
+        // whatever a server sends, an unparsable URL is not worth a crash
+        let url = match Url::parse(url) {
+            Ok(url) => url,
+            Err(_) => return,
+        };
+        // a local page reloading itself should not be added twice
+        if url.scheme() == "file" && self.current.as_ref() == Some(&url) {
+            return;
+        }
         if let Some(c) = self.current.replace(url) {
             self.past.push(c);
             self.future = vec![]
@@ -57,6 +65,10 @@ impl History {
 }
 
 pub fn append(url: &str) {
+    // visiting a feed entry marks it as read
+    if let Ok(url) = Url::parse(url) {
+        crate::feeds::subscriptions::mark_read(&url);
+    }
     HISTORY.lock().unwrap().append(url)
 }
 
@@ -69,13 +81,16 @@ pub fn get_current_host() -> Option<String> {
         .map(String::from)
 }
 
-pub fn get_current_url() -> Option<String> {
+pub fn get_current_scheme() -> Option<String> {
     HISTORY
         .lock()
         .unwrap()
         .current()
-        .and_then(|u| u.join("./").ok())
-        .map(|p| p.to_string())
+        .map(|u| u.scheme().to_string())
+}
+
+pub fn get_current_url() -> Option<Url> {
+    HISTORY.lock().unwrap().current().cloned()
 }
 
 pub fn get_previous_url() -> Option<Url> {
@@ -161,6 +176,57 @@ fn test_append_no_current() {
     );
 }
 
+#[test]
+fn test_append_same_url() {
+    crate::history::clear();
+
+    append("file:///home/capsule/index.gmi");
+    append("file:///home/capsule/index.gmi");
+
+    assert_eq!(
+        *HISTORY.lock().unwrap(),
+        History {
+            past: vec![],
+            current: Some(Url::parse("file:///home/capsule/index.gmi").unwrap()),
+            future: vec![],
+        },
+    );
+}
+
+#[test]
+fn test_append_same_remote_url() {
+    crate::history::clear();
+
+    append("gemini://typed-hole.org/");
+    append("gemini://typed-hole.org/");
+
+    assert_eq!(
+        *HISTORY.lock().unwrap(),
+        History {
+            past: vec![Url::parse("gemini://typed-hole.org/").unwrap()],
+            current: Some(Url::parse("gemini://typed-hole.org/").unwrap()),
+            future: vec![],
+        },
+    );
+}
+
+#[test]
+fn test_append_invalid_url() {
+    crate::history::clear();
+
+    append("gemini://typed-hole.org");
+    append("gemini://[typed-hole.org");
+
+    assert_eq!(
+        *HISTORY.lock().unwrap(),
+        History {
+            past: vec![],
+            current: Some(Url::parse("gemini://typed-hole.org").unwrap()),
+            future: vec![],
+        },
+    );
+}
+
 #[test]
 fn test_get_previous_url_simple() {
     crate::history::clear();
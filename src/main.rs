@@ -6,32 +6,46 @@ extern crate gtk;
 extern crate lazy_static;
 
 use std::env;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::thread;
 use url::Url;
 
 use gtk::prelude::*;
 
 mod gui;
 use gui::Gui;
+mod about;
 mod absolute_url;
 use absolute_url::AbsoluteUrl;
 mod bookmarks;
-mod client;
+mod cli;
 mod colors;
 mod dialog;
+mod downloads;
 mod draw;
-mod finger;
-mod gemini;
-mod gopher;
 mod history;
-mod protocols;
-use protocols::{Finger, Gemini, Gopher, Protocol, Scheme};
+mod render;
 mod settings;
-mod status;
+
+// Protocols and parsers live in castor-core.
+use castor_core::{
+    charset, client, feeds, file, finger, gemini, gopher, html, markdown, nex, protocols, spartan,
+    status, titan,
+};
+use protocols::{About, File, Finger, Gemini, Gopher, Nex, Protocol, Scheme, Spartan, Titan};
 use status::Status;
 
 fn main() {
+    // --dump and friends run without a window
+    let args: Vec<String> = env::args().collect();
+    match cli::parse_args(&args[1..]) {
+        Ok(Some(options)) => std::process::exit(cli::run(&options)),
+        Ok(None) => (),
+        Err(e) => std::process::exit(cli::usage(&e)),
+    }
+
     // Start up the GTK3 subsystem.
     gtk::init().expect("Unable to start GTK3. Error");
 
@@ -93,7 +107,21 @@ fn main() {
         let button = gui.show_bookmarks_button();
         let gui = gui.clone();
         button.connect_clicked(move |_| {
-            show_bookmarks(&gui);
+            visit_url(
+                &gui,
+                About {
+                    source: String::from("about:bookmarks"),
+                },
+            );
+        });
+    }
+
+    // Bind edit button
+    {
+        let button = gui.edit_button();
+        let gui = gui.clone();
+        button.connect_clicked(move |_| {
+            edit_page(&gui);
         });
     }
 
@@ -107,6 +135,25 @@ fn main() {
         });
     }
 
+    // Bind the feed icon of the URL bar
+    {
+        let gui_clone = gui.clone();
+        let url_bar = gui.url_bar();
+        url_bar.set_icon_from_icon_name(
+            gtk::EntryIconPosition::Secondary,
+            Some("application-rss+xml-symbolic"),
+        );
+        url_bar.set_icon_tooltip_text(
+            gtk::EntryIconPosition::Secondary,
+            Some("Subscribe to this page"),
+        );
+        url_bar.connect_icon_press(move |_, position, _| {
+            if position == gtk::EntryIconPosition::Secondary {
+                toggle_subscription(&gui_clone);
+            }
+        });
+    }
+
     // Bind Mouse-Back
     {
         let gui_clone = gui.clone();
@@ -120,7 +167,6 @@ fn main() {
     }
 
     // Use passed URL or settings start_url
-    let args: Vec<String> = env::args().collect();
     match args.len() {
         // no argument passed, check settings
         1 => {
@@ -132,18 +178,30 @@ fn main() {
         _ => route_url(&gui, args[1].to_string()),
     }
 
+    feeds::subscriptions::refresh_in_background();
+
     gui.start();
     gtk::main();
 }
 
 fn route_url(gui: &Arc<Gui>, url: String) {
     if url == "" {
+    } else if url.starts_with("about:") {
+        visit_url(gui, About { source: url })
     } else if url.starts_with("gemini://") {
         visit_url(&gui, Gemini { source: url })
-    } else if url.starts_with("gopher://") {
+    } else if url.starts_with("gopher://") || url.starts_with("gophers://") {
         visit_url(&gui, Gopher { source: url })
     } else if url.starts_with("finger://") {
         visit_url(&gui, Finger { source: url })
+    } else if url.starts_with("nex://") {
+        visit_url(&gui, Nex { source: url })
+    } else if url.starts_with("titan://") {
+        visit_url(&gui, Titan { source: url })
+    } else if url.starts_with("spartan://") {
+        visit_url(&gui, Spartan { source: url })
+    } else if url.starts_with("file://") || url.starts_with('/') {
+        visit_url(&gui, File { source: url })
     } else {
         visit_url(
             &gui,
@@ -168,6 +226,18 @@ fn go_forward(gui: &Arc<Gui>) {
 
 fn visit(gui: &Arc<Gui>, url: &Url) {
     match url.scheme() {
+        "about" => visit_url(
+            gui,
+            About {
+                source: url.to_string(),
+            },
+        ),
+        "file" => visit_url(
+            gui,
+            File {
+                source: url.to_string(),
+            },
+        ),
         "finger" => visit_url(
             gui,
             Finger {
@@ -180,12 +250,24 @@ fn visit(gui: &Arc<Gui>, url: &Url) {
                 source: url.to_string(),
             },
         ),
-        "gopher" => visit_url(
+        "gopher" | "gophers" => visit_url(
             gui,
             Gopher {
                 source: url.to_string(),
             },
         ),
+        "nex" => visit_url(
+            gui,
+            Nex {
+                source: url.to_string(),
+            },
+        ),
+        "spartan" => visit_url(
+            gui,
+            Spartan {
+                source: url.to_string(),
+            },
+        ),
         _ => (),
     }
 }
@@ -199,6 +281,19 @@ fn refresh(gui: &Arc<Gui>) {
 fn update_url_field(gui: &Arc<Gui>, url: &str) {
     let url_bar = gui.url_bar();
     url_bar.get_buffer().set_text(url);
+    update_security_indicator(&gui, false);
+}
+
+// Shows a lock in the URL bar for pages that came over TLS.
+fn update_security_indicator(gui: &Arc<Gui>, secure: bool) {
+    let url_bar = gui.url_bar();
+    let (icon, tooltip) = if secure {
+        ("channel-secure-symbolic", Some("Loaded over TLS"))
+    } else {
+        ("edit-find-symbolic", None)
+    };
+    url_bar.set_icon_from_icon_name(gtk::EntryIconPosition::Primary, Some(icon));
+    url_bar.set_icon_tooltip_text(gtk::EntryIconPosition::Primary, tooltip);
 }
 
 fn add_bookmark(gui: &Arc<Gui>) {
@@ -215,63 +310,302 @@ fn add_bookmark(gui: &Arc<Gui>) {
     }
 }
 
-fn show_bookmarks(gui: &Arc<Gui>) {
-    let content_view = gui.content_view();
+fn toggle_subscription(gui: &Arc<Gui>) {
+    let url_bar = gui.url_bar();
+    let current_url = url_bar.get_text().expect("get_text failed").to_string();
+    let url = match Url::parse(&current_url) {
+        Ok(url) => url,
+        Err(_) => {
+            dialog::error(gui, "\nInvalid feed URL.\n");
+            return;
+        }
+    };
+
+    if feeds::subscriptions::is_subscribed(&url) {
+        match feeds::subscriptions::unsubscribe(&url) {
+            Ok(()) => dialog::info(gui, "Unsubscribed."),
+            Err(e) => dialog::error(gui, &format!("\n{}\n", e)),
+        }
+        return;
+    }
 
-    let bookmarks_list = format!("# Bookmarks\n\n{}", bookmarks::content());
-    let parsed_content = gemini::parser::parse(bookmarks_list);
+    // the file is only written once the page turned out to be a feed
+    let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+    {
+        let url = url.clone();
+        thread::spawn(move || {
+            let _ = sender.send(feeds::subscriptions::fetch(&url));
+        });
+    }
 
-    clear_buffer(&content_view);
-    draw::gemini_content(&gui, parsed_content);
+    render::set_status(gui, "Subscribing...");
+    let gui = gui.clone();
+    receiver.attach(None, move |result| {
+        render::set_status(&gui, "");
+        let result = result.and_then(|feed| {
+            feeds::subscriptions::subscribe(&url, &feed)?;
+            Ok(feed)
+        });
+        match result {
+            Ok(feed) => dialog::info(
+                &gui,
+                &format!(
+                    "Subscribed to {}, {} entries.",
+                    feed.title,
+                    feed.entries.len()
+                ),
+            ),
+            Err(e) => dialog::error(&gui, &format!("\n{}\n", e)),
+        }
+        glib::Continue(false)
+    });
+}
 
-    update_url_field(&gui, "::bookmarks");
+fn edit_page(gui: &Arc<Gui>) {
+    let url_bar = gui.url_bar();
+    let current_url = url_bar.get_text().expect("get_text failed").to_string();
+
+    let urls = Url::parse(&current_url)
+        .ok()
+        .and_then(|url| titan::client::edit_url(&url).map(|titan_url| (url, titan_url)));
+    let (url, titan_url) = match urls {
+        Some(urls) => urls,
+        None => {
+            dialog::error(&gui, "\nOnly Gemini pages can be edited.\n");
+            return;
+        }
+    };
+
+    match client::fetch(&url, settings::max_body_size()) {
+        Ok((meta, source)) => {
+            let mime = meta.split(';').next().unwrap_or("").trim().to_string();
+            if !mime.starts_with("text/") {
+                dialog::error(&gui, &format!("\nCan't edit {} pages.\n", mime));
+                return;
+            }
 
-    content_view.show_all();
+            let source = String::from_utf8_lossy(&source).to_string();
+            if let Some((text, token)) = dialog::editor(&gui, &titan_url, &source) {
+                upload(&gui, &titan_url, &mime, text.as_bytes(), &token);
+            }
+        }
+        Err(e) => {
+            dialog::error(&gui, &format!("\n{}\n", e));
+        }
+    }
 }
 
-pub fn visit_url<T: AbsoluteUrl + Protocol>(gui: &Arc<Gui>, url: T) {
-    if url.get_source_str() == "gemini://::bookmarks" {
-        show_bookmarks(&gui);
-        return;
+// Sends data to a titan:// URL and shows where the server sends us next.
+fn upload(gui: &Arc<Gui>, url: &Url, mime: &str, data: &[u8], token: &str) {
+    let titan = Titan {
+        source: url.to_string(),
+    };
+    match titan::client::upload(titan, mime, token, data) {
+        Ok((meta, body)) => {
+            let meta_str = String::from_utf8_lossy(&meta.unwrap()).to_string();
+
+            match Status::from_str(&meta_str) {
+                Ok(Status::Success(meta)) => {
+                    let page = titan::client::page_url(url).unwrap_or_else(|| url.clone());
+                    show_response(&gui, &page, &meta, body);
+                }
+                Ok(Status::RedirectTemporary(new_url)) | Ok(Status::RedirectPermanent(new_url)) => {
+                    match url.join(&new_url) {
+                        Ok(new_url) if new_url.scheme() == "titan" => {
+                            dialog::info(&gui, "\nUpload complete.\n")
+                        }
+                        Ok(new_url) if new_url.scheme() == "file" => {
+                            dialog::error(gui, "\nCan't follow a redirect to a local file.\n")
+                        }
+                        Ok(new_url) => visit(&gui, &new_url),
+                        Err(e) => dialog::error(&gui, &format!("\n{}\n", e)),
+                    }
+                }
+                _ => {
+                    dialog::error(&gui, &format!("\nUpload failed: {}\n", meta_str.trim()));
+                }
+            }
+        }
+        Err(e) => {
+            dialog::error(&gui, &format!("\n{}\n", e));
+        }
     }
+}
 
+pub fn download(gui: &Arc<Gui>, url: &Url, mime: &str, body: client::Stream) {
+    let name = downloads::file_name(url, mime);
+    let executable = downloads::is_executable(&name, mime);
+
+    match dialog::download(&gui, &name, mime, executable) {
+        dialog::DownloadAction::Save => {
+            if let Some(path) = dialog::save_file(&gui, &downloads::directory(), &name) {
+                start_download(&gui, url, path, false, vec![], body);
+            }
+        }
+        dialog::DownloadAction::Open => {
+            let path = downloads::unique_path(&downloads::directory(), &name);
+            start_download(&gui, url, path, true, vec![], body);
+        }
+        dialog::DownloadAction::Cancel => (),
+    }
+}
+
+// Lists the Gopher+ attributes of an item and opens the view picked.
+pub fn show_item_info(gui: &Arc<Gui>, url: &Url) {
+    let info_url = match gopher::address::with_plus(url, "!") {
+        Some(info_url) => info_url,
+        None => {
+            dialog::error(&gui, &format!("\nInvalid URL {}\n", url));
+            return;
+        }
+    };
+
+    match client::fetch(&info_url, settings::max_body_size()) {
+        Ok((_, content)) => {
+            let attributes = gopher::plus::parse_attributes(&String::from_utf8_lossy(&content));
+            let views = gopher::plus::views(&attributes);
+
+            let text = attributes
+                .iter()
+                .filter(|attribute| attribute.name != "INFO")
+                .map(|attribute| format!("{}\n{}", attribute.name, attribute.value))
+                .collect::<Vec<String>>()
+                .join("\n\n");
+            let labels: Vec<String> = views.iter().map(|view| view.label()).collect();
+
+            if let Some(index) = dialog::item_info(&gui, &text, &labels) {
+                open_view(&gui, url, &views[index]);
+            }
+        }
+        Err(e) => {
+            dialog::error(&gui, &format!("\n{}\n", e));
+        }
+    }
+}
+
+fn open_view(gui: &Arc<Gui>, url: &Url, view: &gopher::plus::View) {
+    let view_url = match gopher::address::with_plus(url, &view.request()) {
+        Some(view_url) => view_url,
+        None => {
+            dialog::error(&gui, &format!("\nInvalid URL {}\n", url));
+            return;
+        }
+    };
+    let source = view_url.to_string();
+
+    if view.mime.starts_with("text/") || view.mime.ends_with("gopher-menu") {
+        visit_url(&gui, Gopher { source });
+    } else {
+        match gopher::client::get_stream(Gopher { source }) {
+            Ok((_meta, body)) => download(&gui, &view_url, &view.mime, body),
+            Err(e) => dialog::error(&gui, &format!("\n{}\n", e)),
+        }
+    }
+}
+
+pub fn start_download(
+    gui: &Arc<Gui>,
+    url: &Url,
+    path: PathBuf,
+    open: bool,
+    received: Vec<u8>,
+    body: client::Stream,
+) {
+    let id = downloads::add(url.as_str(), &path);
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+
+    {
+        let path = path.clone();
+        thread::spawn(move || {
+            let result = downloads::save(&path, &received, body, |size| {
+                downloads::update(id, size);
+                let _ = sender.send(downloads::Progress::Received(size));
+            });
+            downloads::finish(id, &result);
+            let _ = sender.send(downloads::Progress::Finished(result));
+        });
+    }
+
+    let gui = gui.clone();
+    receiver.attach(None, move |progress| match progress {
+        downloads::Progress::Received(size) => {
+            render::set_status(
+                &gui,
+                &format!("Downloading {}: {}", name, client::format_size(size as usize)),
+            );
+            glib::Continue(true)
+        }
+        downloads::Progress::Finished(Ok(size)) => {
+            render::set_status(
+                &gui,
+                &format!(
+                    "Saved {} ({}) to {}",
+                    name,
+                    client::format_size(size as usize),
+                    path.display()
+                ),
+            );
+            if open {
+                if downloads::has_executable_header(&path) {
+                    dialog::info(
+                        &gui,
+                        &format!("\n{} looks like a program and was not opened.\n", name),
+                    );
+                } else if let Err(e) = open::that(&path) {
+                    dialog::error(&gui, &format!("\n{}\n", e));
+                }
+            }
+            glib::Continue(false)
+        }
+        downloads::Progress::Finished(Err(e)) => {
+            render::set_status(&gui, &format!("Failed to download {}", name));
+            dialog::error(&gui, &format!("\n{}\n", e));
+            glib::Continue(false)
+        }
+    });
+}
+
+pub fn visit_url<T: AbsoluteUrl + Protocol>(gui: &Arc<Gui>, url: T) {
     let content_view = gui.content_view();
 
     match url.get_scheme() {
+        Scheme::About => match url.to_absolute_url() {
+            Ok(abs_url) => match about::page(&abs_url) {
+                Some(content) => {
+                    history::append(abs_url.as_str());
+                    update_url_field(gui, abs_url.as_str());
+
+                    render::cancel(gui);
+                    clear_buffer(content_view);
+                    draw::gemini_content(gui, gemini::parser::parse(content));
+                    content_view.show_all();
+                }
+                None => {
+                    dialog::error(gui, &format!("\nNo such page: {}\n", abs_url));
+                }
+            },
+            Err(e) => {
+                dialog::error(gui, &format!("\n{}\n", e));
+            }
+        },
         Scheme::Gemini => {
             let absolute_url = url.to_absolute_url();
 
             match absolute_url {
-                Ok(absolute_url) => match gemini::client::get_data(Gemini {
+                Ok(absolute_url) => match gemini::client::get_stream(Gemini {
                     source: absolute_url.to_string(),
                 }) {
-                    Ok((meta, new_content)) => {
+                    Ok((meta, body)) => {
                         let meta_str = String::from_utf8_lossy(&meta.unwrap()).to_string();
 
                         if let Ok(status) = Status::from_str(&meta_str) {
                             match status {
                                 Status::Success(meta) => {
-                                    if meta.starts_with("text/") {
-                                        // display text files.
-                                        history::append(absolute_url.as_str());
-                                        update_url_field(&gui, absolute_url.as_str());
-                                        let content_str =
-                                            String::from_utf8_lossy(&new_content).to_string();
-
-                                        clear_buffer(&content_view);
-                                        if meta.starts_with("text/gemini") {
-                                            let parsed_content = gemini::parser::parse(content_str);
-                                            draw::gemini_content(&gui, parsed_content);
-                                        } else {
-                                            // just a text file
-                                            draw::gemini_text_content(&gui, content_str.lines());
-                                        }
-
-                                        content_view.show_all();
-                                    } else {
-                                        // download and try to open the rest.
-                                        client::download(new_content);
-                                    }
+                                    show_response(&gui, &absolute_url, &meta, body);
                                 }
                                 Status::Gone(_meta) => {
                                     dialog::error(&gui, "\nSorry page is gone.\n");
@@ -307,17 +641,29 @@ pub fn visit_url<T: AbsoluteUrl + Protocol>(gui: &Arc<Gui>, url: T) {
         Scheme::Gopher => {
             let absolute_url = url.to_absolute_url();
             match absolute_url {
-                Ok(abs_url) => match gopher::client::get_data(url) {
-                    Ok((_meta, new_content)) => {
+                Ok(abs_url) => match gopher::client::get_secure_stream(url) {
+                    Ok((secure, body)) => {
                         history::append(abs_url.as_str());
                         update_url_field(&gui, abs_url.as_str());
-                        let content_str = String::from_utf8_lossy(&new_content).to_string();
+                        update_security_indicator(&gui, secure);
+
+                        let format = match abs_url.host_str() {
+                            _ if is_markdown_file(&abs_url) => render::Format::Markdown,
+                            _ if is_html_item(&abs_url) => {
+                                if settings::html_reader() {
+                                    render::Format::Html
+                                } else {
+                                    render::Format::Text
+                                }
+                            }
+                            Some(host) if settings::gopher_grid_layout(host) => {
+                                render::Format::GopherGrid
+                            }
+                            _ => render::Format::Gopher,
+                        };
 
-                        let parsed_content = gopher::parser::parse(content_str);
                         clear_buffer(&content_view);
-                        draw::gopher_content(&gui, parsed_content);
-
-                        content_view.show_all();
+                        render::stream(&gui, &abs_url, body, format);
                     }
                     Err(e) => {
                         dialog::error(&gui, &format!("\n{}\n", e));
@@ -331,17 +677,127 @@ pub fn visit_url<T: AbsoluteUrl + Protocol>(gui: &Arc<Gui>, url: T) {
         Scheme::Finger => {
             let absolute_url = url.to_absolute_url();
             match absolute_url {
-                Ok(abs_url) => match finger::client::get_data(url) {
-                    Ok((_meta, new_content)) => {
+                Ok(abs_url) => match finger::client::get_stream(url) {
+                    Ok((_meta, body)) => {
                         history::append(abs_url.as_str());
                         update_url_field(&gui, abs_url.as_str());
-                        let content_str = String::from_utf8_lossy(&new_content).to_string();
 
-                        let parsed_content = finger::parser::parse(content_str);
                         clear_buffer(&content_view);
-                        draw::finger_content(&gui, parsed_content);
+                        render::stream(&gui, &abs_url, body, render::Format::Finger);
+                    }
+                    Err(e) => {
+                        dialog::error(&gui, &format!("\n{}\n", e));
+                    }
+                },
+                Err(e) => {
+                    dialog::error(&gui, &format!("\n{}\n", e));
+                }
+            }
+        }
+        Scheme::Nex => {
+            let absolute_url = url.to_absolute_url();
+            match absolute_url {
+                Ok(abs_url) => match nex::client::get_stream(Nex {
+                    source: abs_url.to_string(),
+                }) {
+                    Ok((_meta, body)) => {
+                        history::append(abs_url.as_str());
+                        update_url_field(&gui, abs_url.as_str());
 
-                        content_view.show_all();
+                        clear_buffer(&content_view);
+                        let format = if nex::client::is_directory(&abs_url) {
+                            render::Format::Nex
+                        } else if is_markdown_file(&abs_url) {
+                            render::Format::Markdown
+                        } else {
+                            render::Format::Text
+                        };
+                        render::stream(&gui, &abs_url, body, format);
+                    }
+                    Err(e) => {
+                        dialog::error(&gui, &format!("\n{}\n", e));
+                    }
+                },
+                Err(e) => {
+                    dialog::error(&gui, &format!("\n{}\n", e));
+                }
+            }
+        }
+        Scheme::Titan => match url.to_absolute_url() {
+            Ok(abs_url) => match dialog::upload(&gui, &abs_url) {
+                dialog::UploadAction::Text => {
+                    if let Some((text, token)) = dialog::editor(&gui, &abs_url, "") {
+                        upload(&gui, &abs_url, "text/gemini", text.as_bytes(), &token);
+                    }
+                }
+                dialog::UploadAction::File => {
+                    if let Some((path, token)) = dialog::upload_file(&gui) {
+                        match file::client::read(&path) {
+                            Ok((mime, data)) => upload(&gui, &abs_url, &mime, &data, &token),
+                            Err(e) => dialog::error(&gui, &format!("\n{}\n", e)),
+                        }
+                    }
+                }
+                dialog::UploadAction::Cancel => (),
+            },
+            Err(e) => {
+                dialog::error(&gui, &format!("\n{}\n", e));
+            }
+        },
+        Scheme::Spartan => {
+            let absolute_url = url.to_absolute_url();
+            match absolute_url {
+                Ok(abs_url) => match spartan::client::get_stream(Spartan {
+                    source: abs_url.to_string(),
+                }) {
+                    Ok((meta, body)) => {
+                        let meta_str = String::from_utf8_lossy(&meta.unwrap()).to_string();
+
+                        match spartan::status::Status::from_str(&meta_str) {
+                            Ok(spartan::status::Status::Success(meta)) => {
+                                show_response(&gui, &abs_url, &meta, body);
+                            }
+                            Ok(spartan::status::Status::Redirect(path)) => {
+                                match abs_url.join(&path) {
+                                    Ok(new_url) => visit_url(
+                                        &gui,
+                                        Spartan {
+                                            source: new_url.to_string(),
+                                        },
+                                    ),
+                                    Err(e) => dialog::error(&gui, &format!("\n{}\n", e)),
+                                }
+                            }
+                            Ok(spartan::status::Status::ClientError(message))
+                            | Ok(spartan::status::Status::ServerError(message)) => {
+                                dialog::error(&gui, &format!("\n{}\n", message));
+                            }
+                            Err(_) => {
+                                let message = format!("\nInvalid response: {}\n", meta_str.trim());
+                                dialog::error(&gui, &message);
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        dialog::error(&gui, &format!("\n{}\n", e));
+                    }
+                },
+                Err(e) => {
+                    dialog::error(&gui, &format!("\n{}\n", e));
+                }
+            }
+        }
+        Scheme::File => {
+            let absolute_url = url.to_absolute_url();
+            match absolute_url {
+                Ok(abs_url) => match file::client::get_stream(File {
+                    source: abs_url.to_string(),
+                }) {
+                    Ok((meta, body)) => {
+                        let mime = String::from_utf8_lossy(&meta.unwrap_or_default()).to_string();
+                        if show_response(&gui, &abs_url, &mime, body) {
+                            watch_file(&gui, &abs_url);
+                        }
                     }
                     Err(e) => {
                         dialog::error(&gui, &format!("\n{}\n", e));
@@ -355,9 +811,90 @@ pub fn visit_url<T: AbsoluteUrl + Protocol>(gui: &Arc<Gui>, url: T) {
     }
 }
 
+// Displays a successful response or offers to download what can't be
+// displayed. Returns true when the page was displayed.
+fn show_response(gui: &Arc<Gui>, url: &Url, meta: &str, body: client::Stream) -> bool {
+    let mime = meta.split(';').next().unwrap_or("").trim();
+    let format = if mime == "text/gemini" {
+        render::Format::Gemini
+    } else if mime == "text/markdown" {
+        render::Format::Markdown
+    } else if mime == "text/html" && settings::html_reader() {
+        render::Format::Html
+    } else if feeds::FEED_TYPES.contains(&mime) {
+        render::Format::Feed
+    } else if mime.starts_with("text/") {
+        // just a text file
+        render::Format::Text
+    } else if settings::inline_images() && draw::IMAGE_TYPES.contains(&mime) {
+        render::Format::Image(mime.to_string())
+    } else {
+        // offer to save or open the rest.
+        download(&gui, url, mime, body);
+        return false;
+    };
+
+    history::append(url.as_str());
+    update_url_field(&gui, url.as_str());
+
+    clear_buffer(&gui.content_view());
+    render::stream(&gui, url, body, format);
+    true
+}
+
+// Gopher and Nex don't tell the type of a file, only its name does.
+fn is_markdown_file(url: &Url) -> bool {
+    let path = url.path().to_lowercase();
+    let text_item = match url.scheme() {
+        "gopher" | "gophers" => gopher::address::parse(url).item_type == '0',
+        _ => true,
+    };
+    text_item && (path.ends_with(".md") || path.ends_with(".markdown"))
+}
+
+// Gopher `h` items are HTML pages kept on the server, except the `URL:`
+// ones that point to a page elsewhere.
+fn is_html_item(url: &Url) -> bool {
+    let address = gopher::address::parse(url);
+    address.item_type == 'h' && !address.selector.starts_with("URL:")
+}
+
+// Reloads a local page whenever the file or directory changes, until
+// another page is shown.
+fn watch_file(gui: &Arc<Gui>, url: &Url) {
+    let page = render::current_page();
+    let modified = file::client::modified(url);
+    let gui = gui.clone();
+    let url = url.clone();
+
+    glib::timeout_add_seconds_local(1, move || {
+        if render::current_page() != page {
+            return glib::Continue(false);
+        }
+        if file::client::modified(&url) != modified {
+            visit(&gui, &url);
+            return glib::Continue(false);
+        }
+        glib::Continue(true)
+    });
+}
+
 fn clear_buffer(view: &gtk::TextView) {
     if let Some(buffer) = view.get_buffer() {
         let (mut start, mut end) = buffer.get_bounds();
         buffer.delete(&mut start, &mut end);
+
+        // markup and links of the previous page leave unnamed tags behind
+        if let Some(tags) = buffer.get_tag_table() {
+            let mut unnamed = vec![];
+            tags.foreach(|tag| {
+                if tag.get_property_name().is_none() {
+                    unnamed.push(tag.clone());
+                }
+            });
+            for tag in unnamed {
+                tags.remove(&tag);
+            }
+        }
     }
 }
@@ -0,0 +1,269 @@
+use gtk::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use url::Url;
+
+use crate::charset::{self, Charset};
+use crate::client::{self, format_size, Chunk, Stream};
+use crate::colors::colors::Ansi;
+use crate::dialog;
+use crate::draw;
+use crate::gui::Gui;
+
+// Identifies the page being drawn, chunks arriving for older pages are dropped.
+static PAGE: AtomicUsize = AtomicUsize::new(0);
+
+pub enum Format {
+    Gemini,
+    Markdown,
+    Html,
+    Feed,
+    Text,
+    Gopher,
+    GopherGrid,
+    Finger,
+    Nex,
+    Image(String),
+}
+
+pub fn current_page() -> usize {
+    PAGE.load(Ordering::SeqCst)
+}
+
+// Stops drawing whatever is still being received.
+pub fn cancel(gui: &Arc<Gui>) {
+    PAGE.fetch_add(1, Ordering::SeqCst);
+    set_status(gui, "");
+}
+
+// Reads the body in a background thread and draws complete lines as they arrive.
+pub fn stream(gui: &Arc<Gui>, url: &Url, body: Stream, format: Format) {
+    let page = PAGE.fetch_add(1, Ordering::SeqCst) + 1;
+    let max_size = crate::settings::max_body_size();
+    let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+
+    thread::spawn(move || {
+        client::read_chunks(body, max_size, |chunk| sender.send(chunk).is_ok());
+    });
+
+    let gui = gui.clone();
+    let url = url.clone();
+    let mut received: Vec<u8> = vec![];
+    let mut drawn = 0;
+    let mut mono_toggle = false;
+    let mut charset = page_charset(&url, &format);
+    let mut ansi = Ansi::new();
+
+    set_status(&gui, "Loading...");
+
+    receiver.attach(None, move |chunk| {
+        if PAGE.load(Ordering::SeqCst) != page {
+            return glib::Continue(false);
+        }
+
+        match chunk {
+            Chunk::Data(data) => {
+                received.extend_from_slice(&data);
+                // images, HTML pages and feeds can only be drawn once complete
+                let complete_lines = match format {
+                    Format::Image(_) | Format::Html | Format::Feed => None,
+                    // a markdown paragraph goes on until a blank line
+                    Format::Markdown => received[drawn..]
+                        .windows(2)
+                        .rposition(|pair| pair == b"\n\n" || pair == b"\n\r"),
+                    _ => received[drawn..].iter().rposition(|b| *b == b'\n'),
+                };
+                if let Some(idx) = complete_lines {
+                    let end = drawn + idx + 1;
+                    draw(
+                        &gui,
+                        &url,
+                        &format,
+                        &received[drawn..end],
+                        &mut mono_toggle,
+                        &mut charset,
+                        &mut ansi,
+                    );
+                    drawn = end;
+                }
+                set_status(&gui, &format!("{} received", format_size(received.len())));
+                glib::Continue(true)
+            }
+            Chunk::Done => {
+                draw(
+                    &gui,
+                    &url,
+                    &format,
+                    &received[drawn..],
+                    &mut mono_toggle,
+                    &mut charset,
+                    &mut ansi,
+                );
+                set_status(
+                    &gui,
+                    &format!("Done, {} received", format_size(received.len())),
+                );
+                glib::Continue(false)
+            }
+            Chunk::TooLarge(rest) => {
+                draw(
+                    &gui,
+                    &url,
+                    &format,
+                    &received[drawn..],
+                    &mut mono_toggle,
+                    &mut charset,
+                    &mut ansi,
+                );
+                set_status(
+                    &gui,
+                    &format!(
+                        "Stopped after {}, the limit is {}",
+                        format_size(received.len()),
+                        format_size(max_size)
+                    ),
+                );
+                offer_save(&gui, &url, &format, std::mem::take(&mut received), rest, max_size);
+                glib::Continue(false)
+            }
+            Chunk::Error(e) => {
+                draw(
+                    &gui,
+                    &url,
+                    &format,
+                    &received[drawn..],
+                    &mut mono_toggle,
+                    &mut charset,
+                    &mut ansi,
+                );
+                set_status(
+                    &gui,
+                    &format!("Failed after {} received", format_size(received.len())),
+                );
+                dialog::error(&gui, &format!("\n{}\n", e));
+                glib::Continue(false)
+            }
+        }
+    });
+}
+
+// Gopher and finger pages use the charset set for their host, or the one
+// guessed from their content. Everything else is UTF-8.
+fn page_charset(url: &Url, format: &Format) -> Option<Charset> {
+    match format {
+        Format::Gopher | Format::GopherGrid | Format::Finger => url
+            .host_str()
+            .and_then(crate::settings::charset)
+            .and_then(|name| Charset::from_name(&name)),
+        _ => Some(Charset::Utf8),
+    }
+}
+
+fn draw(
+    gui: &Arc<Gui>,
+    url: &Url,
+    format: &Format,
+    data: &[u8],
+    mono_toggle: &mut bool,
+    charset: &mut Option<Charset>,
+    ansi: &mut Ansi,
+) {
+    if data.is_empty() {
+        return;
+    }
+
+    if charset.is_none() {
+        *charset = charset::detect(data);
+    }
+    let content = charset::decode(data, charset.unwrap_or(Charset::Utf8));
+    match format {
+        Format::Gemini => {
+            let parsed_content = if url.scheme() == "spartan" {
+                crate::gemini::parser::parse_spartan(content)
+            } else {
+                crate::gemini::parser::parse(content)
+            };
+            draw::gemini_lines(&gui, parsed_content, mono_toggle, ansi);
+        }
+        Format::Markdown => {
+            let parsed_content = crate::markdown::parser::parse(&content, mono_toggle);
+            draw::markdown_lines(gui, parsed_content);
+        }
+        Format::Html => {
+            draw::insert_browser_button(gui, url, data);
+            let page = crate::html::gemtext(url, &content);
+            let parsed_content = crate::gemini::parser::parse(page);
+            draw::gemini_lines(gui, parsed_content, mono_toggle, ansi);
+        }
+        // Atom and RSS feeds are shown as gemtext, or as their source when
+        // they can't be read
+        Format::Feed => match crate::feeds::xml::parse(url, &content) {
+            Ok(feed) => {
+                let parsed_content = crate::gemini::parser::parse(crate::feeds::gemtext(&feed));
+                draw::gemini_lines(gui, parsed_content, mono_toggle, ansi);
+            }
+            Err(_) => {
+                draw::gemini_text_content(gui, content.lines(), ansi);
+            }
+        },
+        Format::Text => {
+            draw::gemini_text_content(&gui, content.lines(), ansi);
+        }
+        Format::Gopher => {
+            let parsed_content = crate::gopher::parser::parse(content);
+            draw::gopher_content(&gui, parsed_content, ansi);
+        }
+        Format::GopherGrid => {
+            let parsed_content = crate::gopher::parser::parse(content);
+            draw::gopher_grid_content(&gui, parsed_content, ansi);
+        }
+        Format::Finger => {
+            let parsed_content = crate::finger::parser::parse(content);
+            draw::finger_content(&gui, parsed_content);
+        }
+        Format::Nex => {
+            draw::nex_content(&gui, content.lines(), ansi);
+        }
+        Format::Image(_) => {
+            draw::image(&gui, data);
+        }
+    }
+
+    gui.content_view().show_all();
+}
+
+fn offer_save(
+    gui: &Arc<Gui>,
+    url: &Url,
+    format: &Format,
+    received: Vec<u8>,
+    rest: Stream,
+    max_size: usize,
+) {
+    let message = format!(
+        "\nThis response is larger than {}.\nDo you want to save it to disk?\n",
+        format_size(max_size)
+    );
+    if !dialog::confirm(&gui, &message) {
+        return;
+    }
+
+    let mime = match format {
+        Format::Gemini => "text/gemini",
+        Format::Markdown => "text/markdown",
+        Format::Html => "text/html",
+        Format::Feed => "application/xml",
+        Format::Text | Format::Finger | Format::Nex => "text/plain",
+        Format::Gopher | Format::GopherGrid => "",
+        Format::Image(mime) => mime,
+    };
+    let name = crate::downloads::file_name(url, mime);
+    if let Some(path) = dialog::save_file(&gui, &crate::downloads::directory(), &name) {
+        crate::start_download(&gui, url, path, false, received, rest);
+    }
+}
+
+pub fn set_status(gui: &Arc<Gui>, text: &str) {
+    gui.status_label().set_text(text);
+}